@@ -0,0 +1,31 @@
+#[macro_use]
+extern crate criterion;
+extern crate edn_sphere;
+
+use criterion::Criterion;
+
+// a vector mixing every kind of numeric literal
+fn number_heavy_input() -> String {
+    let numbers: Vec<String> = (0..2000).map(|i| match i % 6 {
+        0 => format!("{}", i * 7919),
+        1 => format!("-{}.{}e{}", i, i % 100, i % 12),
+        2 => format!("0x{:X}", i),
+        3 => format!("{}N", i * 104729),
+        4 => format!("{}.{}M", i, i % 1000),
+        _ => format!("36r{}", i),
+    }).collect();
+    format!("[{}]", numbers.join(" "))
+}
+
+fn tokenize_numbers(c: &mut Criterion) {
+    let input = number_heavy_input();
+    c.bench_function("tokenize numbers", |b| b.iter(|| edn_sphere::tokenize(&input).unwrap()));
+}
+
+fn parse_numbers(c: &mut Criterion) {
+    let input = number_heavy_input();
+    c.bench_function("parse numbers", |b| b.iter(|| edn_sphere::parse(&input).unwrap()));
+}
+
+criterion_group!(benches, tokenize_numbers, parse_numbers);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+#[macro_use]
+extern crate criterion;
+extern crate edn_sphere;
+
+use criterion::Criterion;
+
+// a list of many distinct, namespaced symbols
+fn symbol_heavy_input() -> String {
+    let symbols: Vec<String> = (0..2000).map(|i| format!("my.app.ns{}/handle-event-{}!", i % 7, i)).collect();
+    format!("({})", symbols.join(" "))
+}
+
+fn tokenize_symbols(c: &mut Criterion) {
+    let input = symbol_heavy_input();
+    c.bench_function("tokenize symbols", |b| b.iter(|| edn_sphere::tokenize(&input).unwrap()));
+}
+
+fn parse_symbols(c: &mut Criterion) {
+    let input = symbol_heavy_input();
+    c.bench_function("parse symbols", |b| b.iter(|| edn_sphere::parse(&input).unwrap()));
+}
+
+criterion_group!(benches, tokenize_symbols, parse_symbols);
+criterion_main!(benches);
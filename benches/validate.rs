@@ -0,0 +1,30 @@
+#[macro_use]
+extern crate criterion;
+extern crate edn_sphere;
+
+use criterion::Criterion;
+
+// a long log of events, mostly vectors of scalars
+fn log_input() -> String {
+    let mut input = String::from("[");
+
+    for i in 0..5000 {
+        input.push_str(&format!("[{} :event/login \"user-{}\" [1.5 2.5 {}] {{:ok true}}]\n", i, i, i % 7));
+    }
+
+    input.push(']');
+    input
+}
+
+fn parse_log(c: &mut Criterion) {
+    let input = log_input();
+    c.bench_function("parse log", |b| b.iter(|| edn_sphere::parse(&input).unwrap()));
+}
+
+fn validate_log(c: &mut Criterion) {
+    let input = log_input();
+    c.bench_function("validate log", |b| b.iter(|| edn_sphere::validate(&input).unwrap()));
+}
+
+criterion_group!(benches, parse_log, validate_log);
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+#[macro_use]
+extern crate criterion;
+extern crate edn_sphere;
+
+use criterion::Criterion;
+
+// deeply indented maps, as pretty-printed configuration tends to be
+fn indented_input() -> String {
+    let mut input = String::from("[\n");
+
+    for i in 0..500 {
+        let indent = " ".repeat(4 * (1 + i % 8));
+        input.push_str(&format!("{}{{:id {}\n{}    :name \"entry\",\n{}    :tags [a b c]}}\n", indent, i, indent, indent));
+    }
+
+    input.push(']');
+    input
+}
+
+fn parse_indented(c: &mut Criterion) {
+    let input = indented_input();
+    c.bench_function("parse indented", |b| b.iter(|| edn_sphere::parse(&input).unwrap()));
+}
+
+fn tokenize_indented(c: &mut Criterion) {
+    let input = indented_input();
+    c.bench_function("tokenize indented", |b| b.iter(|| edn_sphere::tokenize(&input).unwrap()));
+}
+
+criterion_group!(benches, parse_indented, tokenize_indented);
+criterion_main!(benches);
@@ -0,0 +1,49 @@
+use prelude::*;
+use Value;
+
+/// Renders `value` as by `Display`, but with the entries of every map and the
+/// elements of every set sorted by `Value`'s `Ord`, so that equal values
+/// always give the same text whatever order their items were written in.
+pub fn to_canonical_string(value: &Value) -> String {
+    value.clone().map_values(|value| match value {
+        Value::Set(mut items) => {
+            items.sort();
+            Value::Set(items)
+        },
+        Value::Map(mut pairs) => {
+            pairs.sort();
+            Value::Map(pairs)
+        },
+        #[cfg(feature = "indexmap")]
+        Value::IndexedMap(mut map) => {
+            map.sort_keys();
+            Value::IndexedMap(map)
+        },
+        value => value,
+    }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parse;
+
+    #[test]
+    fn canonical_string_test() {
+        let canonical = |s| to_canonical_string(&parse(s).unwrap());
+
+        assert_eq!("{:a 1 :b 2 :c 3}", canonical("{:c 3 :a 1 :b 2}"));
+        assert_eq!(canonical("{:b #{3 1 2} :a {:y [2 1] :x nil}}"), canonical("{:a {:x nil :y [2 1]} :b #{2 3 1}}"));
+        assert_eq!("{:a {:x nil :y [2 1]} :b #{1 2 3}}", canonical("{:b #{3 1 2} :a {:y [2 1] :x nil}}"));
+
+        // kinds, then values within a kind
+        assert_eq!(
+            "#{nil false true -2 1/2 1 1.5 2N \\a \"a\" :a ::a :b/a a b/a [] #{} {} #t 1}",
+            canonical("#{#t 1 {} #{} [] b/a a ::a :b/a :a \"a\" \\a 2N 1.5 1 1/2 -2 true false nil}"));
+        assert_eq!("#{1 1.0 ##Inf ##NaN}", canonical("#{##NaN ##Inf 1.0 1}"));
+        assert_eq!("#{-100N -99N 99N 100N}", canonical("#{100N 99N -99N -100N}"));
+
+        // sets and maps are sorted before comparing
+        assert_eq!("#{#{1 3} #{2 3}}", canonical("#{#{3 2} #{3 1}}"));
+    }
+}
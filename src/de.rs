@@ -0,0 +1,299 @@
+use std::fmt;
+
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{Deserialize, DeserializeOwned, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+
+use {parse, ParseError, Value};
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "any EDN value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, i: i64) -> Result<Value, E> {
+        Ok(Value::Integer(i))
+    }
+
+    fn visit_u64<E>(self, u: u64) -> Result<Value, E> {
+        if u > i64::MAX as u64 {
+            Ok(Value::BigInteger(u.to_string()))
+        } else {
+            Ok(Value::Integer(u as i64))
+        }
+    }
+
+    fn visit_f64<E>(self, f: f64) -> Result<Value, E> {
+        Ok(Value::Float(f))
+    }
+
+    fn visit_char<E>(self, c: char) -> Result<Value, E> {
+        Ok(Value::Character(c))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+        // undo `Serialize` writing keywords as `":ns/name"`, which is only
+        // the case when the keyword writes back out as the same text
+        if s.starts_with(':') {
+            if let Ok(keyword @ Value::Keyword { .. }) = parse(s) {
+                if keyword.to_string() == s {
+                    return Ok(keyword);
+                }
+            }
+        }
+
+        Ok(Value::String(String::from(s)))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = vec!();
+
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+
+        Ok(Value::Vector(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut pairs = vec!();
+
+        while let Some(pair) = map.next_entry()? {
+            pairs.push(pair);
+        }
+
+        Ok(Value::Map(pairs))
+    }
+}
+
+/// Rebuilds a value from serde's data model, undoing what `Serialize` can:
+///
+/// * strings that read as a keyword, such as `":ns/name"`, become keywords,
+///   so a string that merely looks like one cannot be told apart
+/// * every sequence becomes a vector; lists and sets are not recovered
+/// * symbols, big numbers, ratios, `#inst`, `#uuid` and other tagged values
+///   come back as the strings and maps they were serialized as
+/// * unsigned integers too large for `i64` become big integers
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+/// Parses `s` as a single EDN value and deserializes a `T` from it.
+///
+/// Keyword map keys name struct fields without their leading `:`. Errors from
+/// the EDN not fitting `T` have no position: their line and character are 0.
+pub fn from_str<T: DeserializeOwned>(s: &str) -> Result<T, ParseError> {
+    from_value(parse(s)?)
+}
+
+/// Deserializes a `T` from an already parsed value.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, ParseError> {
+    T::deserialize(value)
+}
+
+impl<'de> IntoDeserializer<'de, ParseError> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+// keywords and symbols deserialize as their text, keywords without the `:`
+fn into_text(value: Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s),
+        Value::Symbol(s) => Some(s.to_string()),
+        Value::Keyword { namespace: Some(namespace), name, .. } => Some(format!("{}/{}", namespace, name)),
+        Value::Keyword { namespace: None, name, .. } => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = ParseError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParseError> {
+        match self {
+            Value::Nil => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Integer(i) => visitor.visit_i64(i),
+            Value::Float(f) => visitor.visit_f64(f),
+            Value::BigInteger(s) | Value::BigDecimal(s) => visitor.visit_string(s),
+            Value::Character(c) => visitor.visit_char(c),
+            Value::Ratio { .. } => visitor.visit_string(self.to_string()),
+            value @ Value::String(_) | value @ Value::Symbol(_) | value @ Value::Keyword { .. } => {
+                visitor.visit_string(into_text(value).unwrap())
+            },
+            Value::List(items) | Value::Vector(items) | Value::Set(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            },
+            Value::Map(pairs) => {
+                let mut map = MapDeserializer::new(pairs.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            },
+            #[cfg(feature = "indexmap")]
+            Value::IndexedMap(entries) => {
+                let mut map = MapDeserializer::new(entries.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            },
+            Value::Instant(instant) => visitor.visit_string(instant.to_string()),
+            Value::Uuid(uuid) => visitor.visit_string(uuid.to_string()),
+            Value::Tagged { value, .. } => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParseError> {
+        match self {
+            Value::Nil => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, ParseError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // only unit variants, written as a keyword, symbol or string
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V)
+        -> Result<V::Value, ParseError> {
+        let description = self.to_string();
+
+        match into_text(self) {
+            Some(variant) => visitor.visit_enum(variant.into_deserializer()),
+            None => Err(ParseError::new(format!("expected an enum variant, found {}", description), 0, 0)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde::de::Deserialize;
+    use serde_json;
+
+    use super::*;
+    use Value;
+
+    fn keyword(namespace: Option<&str>, name: &str) -> Value {
+        Value::Keyword { namespace: namespace.map(Arc::from), name: Arc::from(name), auto_resolved: false }
+    }
+
+    #[test]
+    fn deserialize_test() {
+        let json: serde_json::Value = serde_json::from_str(
+            r#"{":name": "edn", ":ns/tags": ["sym", 1.5], "plain": [null, true, -3, 18446744073709551615]}"#).unwrap();
+
+        assert_eq!(
+            Value::Map(vec!(
+                (keyword(None, "name"), Value::String(String::from("edn"))),
+                (keyword(Some("ns"), "tags"), Value::Vector(vec!(Value::String(String::from("sym")), Value::Float(1.5)))),
+                (Value::String(String::from("plain")), Value::Vector(vec!(
+                    Value::Nil,
+                    Value::Bool(true),
+                    Value::Integer(-3),
+                    Value::BigInteger(String::from("18446744073709551615"))))))),
+            Value::deserialize(json).unwrap());
+
+        // not a keyword, so left as a string
+        assert_eq!(Value::String(String::from(": x")), serde_json::from_str::<Value>(r#"": x""#).unwrap());
+        assert_eq!(Value::String(String::from(":a ")), serde_json::from_str::<Value>(r#"":a ""#).unwrap());
+        assert_eq!(Value::String(String::from(":a ;x")), serde_json::from_str::<Value>(r#"":a ;x""#).unwrap());
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Level {
+        Debug,
+        Info,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Config {
+        name: String,
+        ratio: f64,
+        enabled: bool,
+        tags: Vec<String>,
+        level: Level,
+        server: Server,
+        backup: Option<Server>,
+        timeout: Option<u32>,
+    }
+
+    #[test]
+    fn from_str_test() {
+        let config: Config = from_str(
+            "{:name \"app\" :ratio 0.5 :enabled true :tags [a :b \"c\"] :level :info
+              :server {:host \"localhost\" :port 8080} :backup nil}").unwrap();
+
+        assert_eq!(
+            Config {
+                name: String::from("app"),
+                ratio: 0.5,
+                enabled: true,
+                tags: vec!(String::from("a"), String::from("b"), String::from("c")),
+                level: Level::Info,
+                server: Server { host: String::from("localhost"), port: 8080 },
+                backup: None,
+                timeout: None,
+            },
+            config);
+
+        assert_eq!(Ok(vec!(Level::Debug)), from_str::<Vec<Level>>("(:debug)"));
+
+        let error = from_str::<Server>("{:host \"h\" :port -1}").unwrap_err();
+        assert!(error.message().contains("-1"));
+        assert!(from_str::<Server>("{:host \"h\"").is_err());
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let value: Value = "{:a [1 2] :b/c \"s\"}".parse().unwrap();
+        assert_eq!(value, serde_json::from_str(&serde_json::to_string(&value).unwrap()).unwrap());
+    }
+}
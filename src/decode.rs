@@ -0,0 +1,81 @@
+use core::str;
+
+use prelude::*;
+
+// Decodes UTF-8 that arrives in arbitrary chunks. A character split across two
+// chunks is held back until the rest of it arrives.
+pub(crate) struct Utf8Decoder {
+    pending: Vec<u8>,
+    offset: usize,
+}
+
+impl Utf8Decoder {
+    pub fn new() -> Utf8Decoder {
+        Utf8Decoder { pending: vec!(), offset: 0 }
+    }
+
+    // appends every complete character to `out`; an error holds the byte
+    // offset of the invalid sequence
+    pub fn push(&mut self, bytes: &[u8], out: &mut String) -> Result<(), usize> {
+        self.pending.extend_from_slice(bytes);
+
+        let valid = match str::from_utf8(&self.pending) {
+            Ok(s) => {
+                out.push_str(s);
+                self.pending.len()
+            },
+            Err(e) => {
+                let valid = e.valid_up_to();
+                out.push_str(str::from_utf8(&self.pending[..valid]).unwrap());
+
+                // `error_len` is `None` when the input merely stops mid-character
+                if e.error_len().is_some() {
+                    return Err(self.offset + valid);
+                }
+
+                valid
+            },
+        };
+
+        self.pending.drain(..valid);
+        self.offset += valid;
+        Ok(())
+    }
+
+    // the input must not end part way through a character
+    pub fn finish(&self) -> Result<(), usize> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(self.offset)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoder_test() {
+        let mut decoder = Utf8Decoder::new();
+        let mut out = String::new();
+
+        for b in "a\u{e9}\u{20ac}\u{1f600}".bytes() {
+            assert_eq!(Ok(()), decoder.push(&[b], &mut out));
+        }
+
+        assert_eq!("a\u{e9}\u{20ac}\u{1f600}", out);
+        assert_eq!(Ok(()), decoder.finish());
+
+        let mut decoder = Utf8Decoder::new();
+        let mut out = String::new();
+        assert_eq!(Ok(()), decoder.push(b"ab\xe2\x82", &mut out));
+        assert_eq!("ab", out);
+        assert_eq!(Err(2), decoder.finish());
+        assert_eq!(Err(2), decoder.push(b"x", &mut out));
+
+        let mut decoder = Utf8Decoder::new();
+        assert_eq!(Err(4), decoder.push(b"nil \xff", &mut String::new()));
+    }
+}
@@ -0,0 +1,88 @@
+use alloc::borrow::Cow;
+use core::fmt;
+
+use prelude::*;
+use {ParseError, Parser, Span, Token};
+
+/// A token together with the text it was written as.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Lexeme<'a> {
+    pub token: Token<'a>,
+    /// Written out in place of the token; replace it to change the output.
+    pub text: Cow<'a, str>,
+    pub span: Span,
+}
+
+/// Every token of a document in order, whitespace, commas and comments
+/// included, so that it can be written back out exactly as it was read.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Document<'a> {
+    lexemes: Vec<Lexeme<'a>>,
+}
+
+impl<'a> Document<'a> {
+    /// Fails at the first lexically invalid token. Unbalanced delimiters are
+    /// kept, as the document is never read into values.
+    pub fn parse(input: &'a str) -> Result<Document<'a>, ParseError> {
+        let mut parser = Parser::new(input);
+        let mut lexemes = vec!();
+
+        while let Some((token, span)) = parser.next_spanned()? {
+            lexemes.push(Lexeme { token, text: Cow::Borrowed(&input[span.start..span.end]), span });
+        }
+
+        Ok(Document { lexemes })
+    }
+
+    pub fn lexemes(&self) -> &[Lexeme<'a>] {
+        &self.lexemes
+    }
+
+    pub fn lexemes_mut(&mut self) -> &mut Vec<Lexeme<'a>> {
+        &mut self.lexemes
+    }
+}
+
+/// Writes out the text of each lexeme in turn.
+impl<'a> fmt::Display for Document<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for lexeme in &self.lexemes {
+            f.write_str(&lexeme.text)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads `input` into a `Document` and writes it back out, which reproduces
+/// it byte for byte.
+pub fn reformat(input: &str) -> Result<String, ParseError> {
+    Document::parse(input).map(|document| document.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reformat_test() {
+        let input = "; config\n{:name \"edn\\n\", :ports [80, 443] ; web\n\t:id #uuid \"f81d4fae-7dec-11d0-a765-00a0c91e6bf6\"\r\n #_ :skipped}\n";
+        assert_eq!(Ok(String::from(input)), reformat(input));
+        assert_eq!(Ok(String::new()), reformat(""));
+        assert!(reformat("\"open").is_err());
+    }
+
+    #[test]
+    fn edit_test() {
+        let mut document = Document::parse("[1 , 2] ; old").unwrap();
+        assert_eq!(Token::Whitespace(" , "), document.lexemes()[2].token);
+
+        for lexeme in document.lexemes_mut() {
+            if let Token::Comment(_) = lexeme.token {
+                lexeme.text = Cow::Owned(String::from("; new"));
+            }
+        }
+
+        assert_eq!("[1 , 2] ; new", document.to_string());
+    }
+}
@@ -0,0 +1,72 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error;
+
+use prelude::*;
+use Span;
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct ParseError {
+    message: String,
+    line: i64,
+    character: i64,
+    span: Option<Span>,
+}
+
+impl ParseError {
+    pub(crate) fn new(message: String, line: i64, character: i64) -> ParseError {
+        ParseError { message, line, character, span: None }
+    }
+
+    pub(crate) fn with_span(self, span: Span) -> ParseError {
+        ParseError { span: Some(span), ..self }
+    }
+
+    // the error as found in text that starts `bytes` into the whole input,
+    // `lines` lines down; `characters` more along if on its first line
+    pub(crate) fn moved(self, bytes: usize, lines: i64, characters: i64) -> ParseError {
+        let (line, character) = match self.line {
+            1 => (1 + lines, self.character + characters),
+            line => (line + lines, self.character),
+        };
+
+        let span = self.span.map(|span| Span { start: span.start + bytes, end: span.end + bytes, line, character });
+        ParseError { line, character, span, ..self }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn line(&self) -> i64 {
+        self.line
+    }
+
+    pub fn character(&self) -> i64 {
+        self.character
+    }
+
+    /// The source text the error is about: the whole of a bad token, such as
+    /// a string left unterminated, or an empty span at the error's position
+    /// when no one piece of text is to blame. Errors that do not come from
+    /// reading text, such as those of deserialization, have none.
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, character {}", self.message, self.line, self.character)
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for ParseError {}
+
+#[cfg(feature = "serde")]
+impl ::serde::de::Error for ParseError {
+    fn custom<T: fmt::Display>(message: T) -> ParseError {
+        ParseError::new(message.to_string(), 0, 0)
+    }
+}
@@ -0,0 +1,278 @@
+use prelude::*;
+use reader::{qualify, Reader};
+use {Delimiter, Parser, ParseError, ReaderOptions, Token, Value};
+
+/// One step through a document, as produced by `Events`.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Event {
+    StartList,
+    EndList,
+    StartVector,
+    EndVector,
+    StartMap,
+    EndMap,
+    StartSet,
+    EndSet,
+    /// Comes before each map key, whose own events follow.
+    MapKey,
+    /// A tag without a handler; the events of its form follow.
+    Tag(String),
+    /// A value read whole: a scalar, or the result of a tag handler, `#inst`
+    /// or `#uuid`.
+    Scalar(Value),
+}
+
+// a collection that has been started but not yet ended
+struct Frame {
+    delimiter: Delimiter,
+    end: Event,
+    items: usize,
+    // the namespace of a `#:ns{...}` map, applied to its keys
+    namespace: Option<String>,
+}
+
+/// The events of a document, read one at a time, so that it can be processed
+/// without building a `Value` for each of its top-level forms.
+///
+/// Limits and errors are those of reading values, except that duplicate map
+/// keys and set elements go unnoticed. Discarded forms and tags with a
+/// handler are read whole. Iteration ends at the end of the input or after
+/// the first error.
+pub struct Events<'a> {
+    reader: Reader<'a>,
+    options: &'a ReaderOptions,
+    stack: Vec<Frame>,
+    // a token held back while its `MapKey` is returned
+    key: Option<Token<'a>>,
+    // the tag whose form is still to come
+    tag: Option<String>,
+    failed: bool,
+}
+
+impl<'a> Events<'a> {
+    pub fn new(input: &'a str, options: &'a ReaderOptions) -> Events<'a> {
+        Events {
+            reader: Reader::new(Parser::with_options(input, options.parser.clone()), options),
+            options,
+            stack: vec!(),
+            key: None,
+            tag: None,
+            failed: false,
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event>, ParseError> {
+        let token = match self.key.take() {
+            Some(token) => token,
+            None => match self.reader.next_token()? {
+                Some(token) => {
+                    if self.announces_key(&token) {
+                        self.key = Some(token);
+                        return Ok(Some(Event::MapKey));
+                    }
+
+                    token
+                },
+                None => return self.end_of_input(),
+            },
+        };
+
+        if let Token::Close(found) = token {
+            return self.close(found).map(Some);
+        }
+
+        // the form of a tag is part of the same item as the tag
+        let tag = self.tag.take();
+        let limit = self.options.parser.max_collection_len;
+
+        if let (None, Some(frame)) = (tag.as_ref(), self.stack.last_mut()) {
+            // a key and its value are one item of a map
+            let count = if frame.end == Event::EndMap { frame.items / 2 } else { frame.items };
+
+            if count >= limit {
+                return Err(self.reader.error(format!("collection exceeds {} items", limit)));
+            }
+
+            frame.items += 1;
+        }
+
+        let event = match token {
+            Token::Open(Delimiter::Paren) => self.open(Delimiter::Paren, Event::StartList, Event::EndList, None)?,
+            Token::Open(Delimiter::Bracket) => self.open(Delimiter::Bracket, Event::StartVector, Event::EndVector, None)?,
+            Token::Open(Delimiter::Brace) => self.open(Delimiter::Brace, Event::StartMap, Event::EndMap, None)?,
+            Token::OpenSet => self.open(Delimiter::Brace, Event::StartSet, Event::EndSet, None)?,
+            Token::NamespacedMap(namespace) => match self.reader.next_token()? {
+                Some(Token::Open(Delimiter::Brace)) => {
+                    self.open(Delimiter::Brace, Event::StartMap, Event::EndMap, Some(namespace))?
+                },
+                _ => return Err(self.reader.error(format!("expected a map after #:{}", namespace))),
+            },
+            Token::Tag(tag) => {
+                if self.options.tag_handler(&tag).is_some() || (tag == "inst") || (tag == "uuid") {
+                    Event::Scalar(self.reader.read_tagged(tag)?)
+                } else {
+                    self.tag = Some(tag.clone());
+                    Event::Tag(tag)
+                }
+            },
+            token => {
+                let value = Value::from_token(token, self.options.parser.interner.as_ref())
+                    .ok_or_else(|| self.reader.error(String::from("invalid value")))?;
+
+                match self.stack.last() {
+                    Some(&Frame { namespace: Some(ref namespace), items, .. }) if tag.is_none() && (items % 2 == 1) => {
+                        Event::Scalar(qualify(value, namespace))
+                    },
+                    _ => Event::Scalar(value),
+                }
+            },
+        };
+
+        Ok(Some(event))
+    }
+
+    // whether `token` starts a key of the innermost collection, a map
+    fn announces_key(&self, token: &Token) -> bool {
+        match (self.stack.last(), token) {
+            (_, Token::Close(_)) => false,
+            (Some(frame), _) => (frame.end == Event::EndMap) && (frame.items % 2 == 0) && self.tag.is_none(),
+            (None, _) => false,
+        }
+    }
+
+    fn open(&mut self, delimiter: Delimiter, start: Event, end: Event, namespace: Option<String>) -> Result<Event, ParseError> {
+        let max_depth = self.options.parser.max_depth;
+
+        if self.stack.len() >= max_depth {
+            return Err(self.reader.error(format!("collections nested deeper than {} levels", max_depth)));
+        }
+
+        self.stack.push(Frame { delimiter, end, items: 0, namespace });
+        Ok(start)
+    }
+
+    fn close(&mut self, found: Delimiter) -> Result<Event, ParseError> {
+        if let Some(tag) = self.tag.take() {
+            return Err(self.reader.error(format!("expected a form after tag #{}", tag)));
+        }
+
+        let frame = match self.stack.pop() {
+            Some(frame) => frame,
+            None => return Err(self.reader.error(format!("unexpected closing delimiter `{}`", found.close()))),
+        };
+
+        if found != frame.delimiter {
+            return Err(self.reader.error(format!(
+                "unexpected closing delimiter `{}`, expected `{}`", found.close(), frame.delimiter.close())));
+        }
+
+        if (frame.end == Event::EndMap) && (frame.items % 2 != 0) {
+            return Err(self.reader.error(String::from("map literal contains an odd number of forms")));
+        }
+
+        Ok(frame.end)
+    }
+
+    fn end_of_input(&mut self) -> Result<Option<Event>, ParseError> {
+        if let Some(tag) = self.tag.take() {
+            return Err(self.reader.error(format!("expected a form after tag #{}", tag)));
+        }
+
+        match self.stack.last() {
+            Some(frame) => Err(self.reader.error(format!("unexpected end of input, expected `{}`", frame.delimiter.close()))),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Result<Event, ParseError>;
+
+    fn next(&mut self) -> Option<Result<Event, ParseError>> {
+        if self.failed {
+            return None;
+        }
+
+        let result = self.next_event();
+        self.failed = result.is_err();
+        result.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(s: &str) -> Result<Vec<Event>, ParseError> {
+        Events::new(s, &ReaderOptions::default()).collect()
+    }
+
+    fn keyword(namespace: Option<&str>, name: &str) -> Event {
+        Event::Scalar(Value::Keyword { namespace: namespace.map(|ns| ns.into()), name: name.into(), auto_resolved: false })
+    }
+
+    #[test]
+    fn events_test() {
+        assert_eq!(
+            Ok(vec!(
+                Event::StartVector,
+                Event::Scalar(Value::Integer(1)),
+                Event::StartMap,
+                Event::MapKey,
+                keyword(None, "a"),
+                Event::Scalar(Value::Integer(2)),
+                Event::EndMap,
+                Event::EndVector)),
+            events("[1 {:a 2}]"));
+
+        assert_eq!(
+            Ok(vec!(
+                Event::StartList,
+                Event::Tag(String::from("point")),
+                Event::StartSet,
+                Event::Scalar(Value::Integer(1)),
+                Event::EndSet,
+                Event::EndList,
+                Event::StartMap,
+                Event::MapKey,
+                keyword(Some("p"), "x"),
+                keyword(None, "y"),
+                Event::MapKey,
+                Event::StartVector,
+                Event::EndVector,
+                Event::Scalar(Value::Nil),
+                Event::EndMap)),
+            events("(#point #{1} #_ [2]) #:p{:x :y [] nil}"));
+
+        let instant = events("#inst \"1985-04-12T23:20:50.52Z\"").unwrap();
+        assert_eq!("instant", match instant[..] {
+            [Event::Scalar(ref value)] => value.type_name(),
+            _ => "",
+        });
+    }
+
+    #[test]
+    fn events_error_test() {
+        let options = ReaderOptions::default();
+        let mut stream = Events::new("[1 {:a}] 2", &options);
+        assert_eq!(Some(Ok(Event::StartVector)), stream.next());
+        assert_eq!(4, stream.by_ref().take_while(|event| event.is_ok()).count());
+        assert_eq!(None, stream.next());
+
+        assert_eq!("map literal contains an odd number of forms", events("{:a}").unwrap_err().message());
+        assert_eq!("unexpected closing delimiter `)`, expected `]`", events("[1)").unwrap_err().message());
+        assert_eq!("unexpected end of input, expected `]`", events("[[]").unwrap_err().message());
+        assert_eq!("expected a form after tag #x", events("[#x]").unwrap_err().message());
+
+        let mut options = ReaderOptions::default();
+        options.parser.max_depth = 1;
+        let error = Events::new("[[]]", &options).collect::<Result<Vec<_>, _>>().unwrap_err();
+        assert_eq!("collections nested deeper than 1 levels", error.message());
+
+        let mut options = ReaderOptions::default();
+        options.parser.max_collection_len = 2;
+        assert!(Events::new("{:a 1 :b 2}", &options).collect::<Result<Vec<_>, _>>().is_ok());
+        let error = Events::new("{:a 1 :b 2 :c 3}", &options).collect::<Result<Vec<_>, _>>().unwrap_err();
+        assert_eq!("collection exceeds 2 items", error.message());
+    }
+}
@@ -0,0 +1,206 @@
+use core::fmt;
+
+/// An RFC-3339 timestamp as read from an `#inst` tagged literal.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub struct Instant {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    nanosecond: u32,
+    offset_minutes: i16,
+}
+
+fn digits(bytes: &[u8], start: usize, len: usize) -> Option<u32> {
+    let digits = bytes.get(start..start + len)?;
+
+    if !digits.iter().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(digits.iter().fold(0, |acc, b| (acc * 10) + u32::from(b - b'0')))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Instant {
+    /// Accepts the full `date-time` production of RFC-3339, e.g.
+    /// `1985-04-12T23:20:50.52Z` or `1996-12-19T16:39:57-08:00`.
+    pub fn parse(s: &str) -> Option<Instant> {
+        let bytes = s.as_bytes();
+        let expect = |i: usize, options: &[u8]| bytes.get(i).is_some_and(|b| options.contains(b));
+
+        if !(expect(4, b"-") && expect(7, b"-") && expect(10, b"Tt") && expect(13, b":") && expect(16, b":")) {
+            return None;
+        }
+
+        let mut instant = Instant {
+            year: digits(bytes, 0, 4)? as u16,
+            month: digits(bytes, 5, 2)? as u8,
+            day: digits(bytes, 8, 2)? as u8,
+            hour: digits(bytes, 11, 2)? as u8,
+            minute: digits(bytes, 14, 2)? as u8,
+            second: digits(bytes, 17, 2)? as u8,
+            nanosecond: 0,
+            offset_minutes: 0,
+        };
+
+        let mut i = 19;
+
+        if expect(i, b".") {
+            let start = i + 1;
+            i = start;
+
+            while expect(i, b"0123456789") {
+                i += 1;
+            }
+
+            if i == start {
+                return None;
+            }
+
+            // digits past nanosecond precision are dropped
+            let len = (i - start).min(9);
+            instant.nanosecond = digits(bytes, start, len)? * 10u32.pow(9 - len as u32);
+        }
+
+        if expect(i, b"Zz") {
+            i += 1;
+        } else if expect(i, b"+-") && expect(i + 3, b":") {
+            let hours = digits(bytes, i + 1, 2)? as i16;
+            let minutes = digits(bytes, i + 4, 2)? as i16;
+
+            if (hours > 23) || (minutes > 59) {
+                return None;
+            }
+
+            instant.offset_minutes = if bytes[i] == b'-' { -(hours * 60 + minutes) } else { hours * 60 + minutes };
+            i += 6;
+        } else {
+            return None;
+        }
+
+        let valid = (i == bytes.len()) &&
+            (instant.month >= 1) && (instant.month <= 12) &&
+            (instant.day >= 1) && (instant.day <= days_in_month(instant.year, instant.month)) &&
+            (instant.hour <= 23) && (instant.minute <= 59) &&
+            (instant.second <= 60); // leap seconds
+
+        if valid {
+            Some(instant)
+        } else {
+            None
+        }
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    pub fn nanosecond(&self) -> u32 {
+        self.nanosecond
+    }
+
+    /// The UTC offset in minutes, `0` for `Z`.
+    pub fn offset_minutes(&self) -> i16 {
+        self.offset_minutes
+    }
+}
+
+/// The RFC-3339 form, with the fraction trimmed of trailing zeros.
+impl fmt::Display for Instant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", self.year, self.month, self.day, self.hour, self.minute, self.second)?;
+
+        if self.nanosecond != 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+
+        if self.offset_minutes == 0 {
+            write!(f, "Z")
+        } else {
+            let sign = if self.offset_minutes < 0 { '-' } else { '+' };
+            let offset = self.offset_minutes.abs();
+            write!(f, "{}{:02}:{:02}", sign, offset / 60, offset % 60)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prelude::*;
+
+    #[test]
+    fn parse_test() {
+        let instant = Instant::parse("1985-04-12T23:20:50Z").unwrap();
+        assert_eq!((1985, 4, 12), (instant.year(), instant.month(), instant.day()));
+        assert_eq!((23, 20, 50), (instant.hour(), instant.minute(), instant.second()));
+        assert_eq!((0, 0), (instant.nanosecond(), instant.offset_minutes()));
+
+        let instant = Instant::parse("1985-04-12T23:20:50.52Z").unwrap();
+        assert_eq!(520_000_000, instant.nanosecond());
+        assert_eq!("1985-04-12T23:20:50.52Z", instant.to_string());
+
+        let instant = Instant::parse("2001-01-01t00:00:00.1234567891z").unwrap();
+        assert_eq!(123_456_789, instant.nanosecond());
+
+        let instant = Instant::parse("1996-12-19T16:39:57-08:00").unwrap();
+        assert_eq!(-480, instant.offset_minutes());
+        assert_eq!("1996-12-19T16:39:57-08:00", instant.to_string());
+
+        let instant = Instant::parse("1990-12-31T15:59:60+05:30").unwrap();
+        assert_eq!((60, 330), (instant.second(), instant.offset_minutes()));
+
+        assert!(Instant::parse("2000-02-29T00:00:00Z").is_some());
+    }
+
+    #[test]
+    fn invalid_test() {
+        assert_eq!(None, Instant::parse("not-a-date"));
+        assert_eq!(None, Instant::parse(""));
+        assert_eq!(None, Instant::parse("1985-04-12"));
+        assert_eq!(None, Instant::parse("1985-04-12T23:20:50"));
+        assert_eq!(None, Instant::parse("1985-04-12 23:20:50Z"));
+        assert_eq!(None, Instant::parse("1985-13-12T23:20:50Z"));
+        assert_eq!(None, Instant::parse("1985-00-12T23:20:50Z"));
+        assert_eq!(None, Instant::parse("1900-02-29T00:00:00Z"));
+        assert_eq!(None, Instant::parse("1985-04-31T23:20:50Z"));
+        assert_eq!(None, Instant::parse("1985-04-12T24:00:00Z"));
+        assert_eq!(None, Instant::parse("1985-04-12T23:20:50.Z"));
+        assert_eq!(None, Instant::parse("1985-04-12T23:20:50+8:00"));
+        assert_eq!(None, Instant::parse("1985-04-12T23:20:50+24:00"));
+        assert_eq!(None, Instant::parse("1985-04-12T23:20:50Zjunk"));
+        assert_eq!(None, Instant::parse("1985-04-12T23:20:5\u{e9}Z"));
+    }
+}
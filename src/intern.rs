@@ -0,0 +1,107 @@
+use alloc::collections::BTreeSet;
+#[cfg(not(feature = "std"))]
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use core::cell::RefCell;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+// without `std` there is no `Mutex`, so the table cannot be shared between
+// threads
+#[cfg(feature = "std")]
+type Names = Arc<Mutex<BTreeSet<Arc<str>>>>;
+#[cfg(not(feature = "std"))]
+type Names = Rc<RefCell<BTreeSet<Arc<str>>>>;
+
+/// Shares the names of symbols and keywords between the values read with it,
+/// so a name repeated throughout a document is stored once.
+///
+/// Clones share one table, so an interner set in `ParserOptions` keeps
+/// working across every parse made with those options.
+#[derive(Debug,Clone,Default)]
+pub struct Interner {
+    names: Names,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    #[cfg(feature = "std")]
+    fn with_names<R, F: FnOnce(&mut BTreeSet<Arc<str>>) -> R>(&self, f: F) -> R {
+        f(&mut self.names.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn with_names<R, F: FnOnce(&mut BTreeSet<Arc<str>>) -> R>(&self, f: F) -> R {
+        f(&mut self.names.borrow_mut())
+    }
+
+    /// The shared copy of `name`, added on first use.
+    pub fn intern(&self, name: &str) -> Arc<str> {
+        self.with_names(|names| {
+            if let Some(shared) = names.get(name) {
+                return shared.clone();
+            }
+
+            let shared: Arc<str> = Arc::from(name);
+            names.insert(shared.clone());
+            shared
+        })
+    }
+
+    /// How many distinct names have been interned.
+    pub fn len(&self) -> usize {
+        self.with_names(|names| names.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prelude::*;
+    use {parse_with_options, ReaderOptions, Value};
+
+    #[test]
+    fn intern_test() {
+        let interner = Interner::new();
+        assert!(Arc::ptr_eq(&interner.intern("type"), &interner.clone().intern("type")));
+        assert!(!Arc::ptr_eq(&interner.intern("type"), &interner.intern("kind")));
+        assert_eq!(2, interner.len());
+    }
+
+    #[test]
+    fn interned_values_test() {
+        let names = |value: &Value| -> Vec<Arc<str>> {
+            value.as_vector().unwrap().iter().map(|v| match *v {
+                Value::Keyword { ref name, .. } => name.clone(),
+                Value::Symbol(ref name) => name.clone(),
+                _ => unreachable!(),
+            }).collect()
+        };
+        let input = "[:type :type :ns/type type type]";
+
+        let mut options = ReaderOptions::default();
+        options.parser.interner = Some(Interner::new());
+        let value = parse_with_options(input, &options).unwrap();
+        let interned = names(&value);
+        assert!(Arc::ptr_eq(&interned[0], &interned[1]));
+        assert!(Arc::ptr_eq(&interned[0], &interned[2]));
+        assert!(Arc::ptr_eq(&interned[0], &interned[3]));
+        assert_eq!(2, options.parser.interner.as_ref().unwrap().len());
+
+        // a second document shares the names of the first
+        let again = names(&parse_with_options("[:type]", &options).unwrap());
+        assert!(Arc::ptr_eq(&interned[0], &again[0]));
+
+        let plain = names(&parse_with_options(input, &ReaderOptions::default()).unwrap());
+        assert!(!Arc::ptr_eq(&plain[0], &plain[1]));
+        assert_eq!(value, parse_with_options(input, &ReaderOptions::default()).unwrap());
+    }
+}
@@ -0,0 +1,44 @@
+use serde_json;
+
+use prelude::*;
+use {parse, ParseError};
+
+/// Parses `input` as a single EDN value and returns it as JSON, for callers
+/// such as JavaScript under WebAssembly that have no use for `Value` itself.
+///
+/// The result is always a JSON object: `{"value": ...}` holding the value as
+/// written by its `Serialize` impl, or `{"error": {"message": ..., "line":
+/// ..., "character": ...}}`. A value JSON cannot hold, such as a map with
+/// keys that are not strings, is an error at line 0, character 0.
+pub fn parse_to_json(input: &str) -> String {
+    let result = parse(input).and_then(|value| {
+        serde_json::to_value(&value).map_err(|error| ParseError::new(error.to_string(), 0, 0))
+    });
+
+    let object = match result {
+        Ok(value) => serde_json::json!({ "value": value }),
+        Err(error) => serde_json::json!({
+            "error": { "message": error.message(), "line": error.line(), "character": error.character() },
+        }),
+    };
+
+    object.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_to_json_test() {
+        assert_eq!(r#"{"value":{":name":"edn",":tags":["a",1]}}"#, parse_to_json("{:name \"edn\" :tags [a 1]}"));
+
+        assert_eq!(
+            r#"{"error":{"character":4,"line":1,"message":"unexpected end of input, expected `]`"}}"#,
+            parse_to_json("[1 2"));
+
+        assert_eq!(
+            r#"{"error":{"character":0,"line":0,"message":"key must be a string"}}"#,
+            parse_to_json("{[1] 2}"));
+    }
+}
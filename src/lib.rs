@@ -1,40 +1,248 @@
-use std::str::Chars;
+// Only reading from `io::Read` needs `std`; everything else runs on `alloc`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg_attr(not(feature = "std"), macro_use)]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate core;
+#[cfg(feature = "indexmap")]
+extern crate indexmap;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(any(feature = "json", all(test, feature = "serde")))]
+extern crate serde_json;
+
+use core::fmt;
+use core::str::{self, Chars};
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+
+mod canonical;
+#[cfg(feature = "serde")]
+mod de;
+mod decode;
+mod document;
+mod error;
+mod events;
+mod instant;
+mod intern;
+#[cfg(feature = "json")]
+mod json;
+mod options;
+mod pretty;
+mod prelude;
+mod push;
+mod reader;
+#[cfg(feature = "serde")]
+mod ser;
+mod span;
+mod uuid;
+mod value;
+mod visit;
+
+pub use canonical::to_canonical_string;
+#[cfg(feature = "serde")]
+pub use de::{from_str, from_value};
+pub use document::{reformat, Document, Lexeme};
+pub use error::ParseError;
+pub use events::{Event, Events};
+pub use instant::Instant;
+pub use intern::Interner;
+#[cfg(feature = "json")]
+pub use json::parse_to_json;
+pub use options::{ColumnMode, DuplicateElementPolicy, DuplicateKeyPolicy, MapBacking, ParserOptions, ReaderOptions, TagHandler};
+pub use pretty::{pretty_print, PrettyOptions};
+pub use push::PushParser;
+pub use reader::{Reader, Values};
+pub use span::Span;
+pub use uuid::Uuid;
+pub use value::{Entries, Items, PathSegment, Value};
+pub use visit::{walk, Visitor};
+
+use prelude::*;
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Delimiter {
+    Paren,
+    Bracket,
+    Brace,
+}
+
+const DELIMITERS: [Delimiter; 3] = [Delimiter::Paren, Delimiter::Bracket, Delimiter::Brace];
+
+impl Delimiter {
+    pub fn open(&self) -> char {
+        match *self {
+            Delimiter::Paren => '(',
+            Delimiter::Bracket => '[',
+            Delimiter::Brace => '{',
+        }
+    }
+
+    pub fn close(&self) -> char {
+        match *self {
+            Delimiter::Paren => ')',
+            Delimiter::Bracket => ']',
+            Delimiter::Brace => '}',
+        }
+    }
+
+    fn opened_by(c: &char) -> Option<Delimiter> {
+        DELIMITERS.iter().find(|d| d.open() == *c).cloned()
+    }
+
+    fn closed_by(c: &char) -> Option<Delimiter> {
+        DELIMITERS.iter().find(|d| d.close() == *c).cloned()
+    }
+}
 
 #[derive(Debug,Clone,PartialEq)]
-pub enum Token {
+pub enum Token<'a> {
     Nil,
     Boolean(bool),
-    Whitespace(Vec<char>),
-    Symbol(Vec<char>),
-    String(Vec<char>),
-    Discard(Vec<char>),
+    Integer(i64),
+    BigInteger(String),
+    Float(f64),
+    BigDecimal(String),
+    // in lowest terms, with a denominator greater than 1
+    Ratio { numerator: i64, denominator: i64 },
+    Whitespace(&'a str),
+    // only produced when `ParserOptions::comma_tokens` is set
+    Comma,
+    Comment(String),
+    Symbol { namespace: Option<&'a str>, name: &'a str },
+    String(String),
+    Character(char),
+    Keyword { namespace: Option<String>, name: String, auto_resolved: bool },
+    Open(Delimiter),
+    OpenSet,
+    Close(Delimiter),
+    Discard,
+    Tag(String),
+    // the `#:ns` prefix of a namespaced map, which the map itself follows
+    NamespacedMap(String),
 }
 
 pub struct Parser<'a> {
-    source: &'a String,
+    source: &'a str,
     iterator: Chars<'a>,
     current_character: Option<char>,
     character: i64,
     line: i64,
+    // byte offset of `current_character`
+    offset: usize,
+    // tokens produced so far, checked against `options.max_total_tokens`
+    tokens: usize,
+    // byte offset just past the last token produced
+    token_end: usize,
+    // set once iteration has returned an error, which ends it
+    failed: bool,
+    options: ParserOptions,
+    scalar_parsers: ScalarParsers,
+}
+
+/// The token layer under `Reader`, for tools such as syntax highlighters
+/// that want the tokens rather than values. Its `next_token` and `Iterator`
+/// give every token, whitespace and comments included.
+pub type Lexer<'a> = Parser<'a>;
+
+// positions only; the source and options are left out
+impl<'a> fmt::Debug for Parser<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Parser")
+            .field("current_character", &self.current_character)
+            .field("line", &self.line)
+            .field("character", &self.character)
+            .field("offset", &self.offset)
+            .finish_non_exhaustive()
+    }
 }
 
-trait TokenParser {
+// The parsers every scalar is given to, made once per `Parser` and reset
+// before each scalar rather than made again.
+struct ScalarParsers {
+    // those added with `add_token_parser`, tried first
+    custom: Vec<Box<dyn TokenParser>>,
+    nil: KeywordTokenParser<'static>,
+    true_: KeywordTokenParser<'static>,
+    false_: KeywordTokenParser<'static>,
+    integer: IntegerTokenParser,
+    float: FloatTokenParser,
+    ratio: RatioParser,
+    symbol: SymbolParser,
+    keyword: KeywordParser,
+}
+
+impl ScalarParsers {
+    fn new(options: &ParserOptions) -> ScalarParsers {
+        ScalarParsers {
+            custom: vec!(),
+            nil: KeywordTokenParser::new("nil", Token::Nil),
+            true_: KeywordTokenParser::new("true", Token::Boolean(true)),
+            false_: KeywordTokenParser::new("false", Token::Boolean(false)),
+            integer: if options.strict_numbers { IntegerTokenParser::strict() } else { IntegerTokenParser::new() },
+            float: FloatTokenParser::new(),
+            ratio: RatioParser::new(),
+            symbol: if options.allow_comments { SymbolParser::new() } else { SymbolParser::with_semicolons() },
+            keyword: if options.allow_comments { KeywordParser::new() } else { KeywordParser::with_semicolons() },
+        }
+    }
+
+    fn all(&mut self) -> impl Iterator<Item = &mut dyn TokenParser> {
+        let built_in: [&mut dyn TokenParser; 8] = [
+            &mut self.nil,
+            &mut self.true_,
+            &mut self.false_,
+            &mut self.integer,
+            &mut self.float,
+            &mut self.ratio,
+            &mut self.symbol,
+            &mut self.keyword,
+        ];
+
+        self.custom.iter_mut().map(|p| &mut **p as &mut dyn TokenParser).chain(built_in)
+    }
+}
+
+/// Recognizes one kind of scalar, such as `nil` or an integer.
+///
+/// A parser is given each character of a scalar in order, one call to
+/// `matches` per character, and is then asked for the token once the scalar
+/// ends. It is reset before every scalar, so one parser serves a whole input.
+/// It sees every scalar, so it must give no token for text it does not
+/// recognize. The first parser to give a token wins.
+pub trait TokenParser {
+    /// Takes the next character, returning whether everything given so far
+    /// could still be, or already is, a scalar of this kind.
     fn matches(&mut self, c: &char) -> bool;
-    fn get_token(&self) -> Option<Token>;
+
+    /// The token for the whole scalar, if it is of this kind. `text` is the
+    /// source of the characters given to `matches`.
+    fn get_token<'s>(&self, text: &'s str) -> Option<Token<'s>>;
+
+    /// Forgets every character given so far, as though newly made.
+    fn reset(&mut self);
 }
 
+#[derive(Debug,Clone)]
 pub struct KeywordTokenParser<'a> {
+    keyword: &'a str,
     iter: Chars<'a>,
-    result: Token,
+    result: Token<'static>,
     last_state: Option<bool>,
 }
 
 
 impl<'a> KeywordTokenParser<'a> {
-    fn new(keyword: &'a str, result: Token) -> KeywordTokenParser<'a> {
+    fn new(keyword: &'a str, result: Token<'static>) -> KeywordTokenParser<'a> {
         KeywordTokenParser {
+            keyword,
             iter: keyword.chars(),
-            result: result,
+            result,
             last_state: None,
         }
     }
@@ -56,68 +264,163 @@ impl<'a> TokenParser for KeywordTokenParser<'a> {
             self.last_state = Some(local_state);
         }
 
-        return self.last_state.unwrap();
+        self.last_state.unwrap()
     }
 
-    fn get_token(&self) -> Option<Token> {
+    fn get_token<'s>(&self, _text: &'s str) -> Option<Token<'s>> {
         if let Some(s) = self.last_state {
-            if s {
+            // a prefix such as `fa` is not `false`
+            if s && self.iter.clone().next().is_none() {
                 return Some(self.result.clone());
             }
         }
 
-        return None;
+        None
+    }
+
+    fn reset(&mut self) {
+        self.iter = self.keyword.chars();
+        self.last_state = None;
     }
 }
 
+#[derive(Debug,Clone)]
 pub struct StringParser {
     last_state: Option<bool>,
+    started: bool,
     terminated: bool,
     escaped: bool,
-    result: Vec<char>,
+    unicode: Option<String>,
+    error: Option<String>,
+    result: String,
 }
 
 impl StringParser {
     pub fn new() -> StringParser {
-        StringParser { escaped: false, terminated: false, last_state: None, result: vec!() }
+        StringParser {
+            escaped: false,
+            started: false,
+            terminated: false,
+            last_state: None,
+            unicode: None,
+            error: None,
+            result: String::new(),
+        }
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+
+    /// Describes why the characters seen so far are not a valid string.
+    pub fn error(&self) -> Option<String> {
+        if self.error.is_some() {
+            self.error.clone()
+        } else if self.started && !self.terminated {
+            Some(String::from("unterminated string"))
+        } else {
+            None
+        }
+    }
+
+    fn unescape(c: &char) -> Option<char> {
+        match *c {
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            'f' => Some('\u{c}'),
+            _ => None,
+        }
+    }
+
+    fn fail(&mut self, message: String) -> bool {
+        if self.error.is_none() {
+            self.error = Some(message);
+        }
+
+        false
+    }
+
+    // keeps tracking quotes and escapes after a failure so the caller can
+    // still find where the string ends
+    fn push(&mut self, c: &char) -> bool {
+        if ! self.started {
+            self.started = *c == '"';
+            return self.started;
+        }
+
+        if let Some(mut hex) = self.unicode.take() {
+            if c.is_ascii_hexdigit() {
+                hex.push(*c);
+
+                if hex.len() < 4 {
+                    self.unicode = Some(hex);
+                    return true;
+                }
+
+                return match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(ch) => {
+                        self.result.push(ch);
+                        true
+                    },
+                    None => self.fail(format!("invalid unicode escape \\u{}: not a unicode scalar value", hex)),
+                };
+            }
+
+            // the escape was cut short, but `c` may still close the string
+            self.fail(format!("invalid unicode escape \\u{}: expected four hex digits", hex));
+            self.push(c);
+            return false;
+        }
+
+        if self.escaped {
+            self.escaped = false;
+
+            if *c == 'u' {
+                self.unicode = Some(String::new());
+                return true;
+            }
+
+            return match StringParser::unescape(c) {
+                Some(e) => {
+                    self.result.push(e);
+                    true
+                },
+                None => self.fail(format!("invalid escape sequence \\{}", c)),
+            };
+        }
+
+        match *c {
+            '\\' => self.escaped = true,
+            '"' => self.terminated = true,
+            _ => self.result.push(*c),
+        }
+
+        true
+    }
+}
+
+impl Default for StringParser {
+    fn default() -> StringParser {
+        StringParser::new()
     }
 }
 
 impl TokenParser for StringParser {
-    fn get_token(&self) -> Option<Token> {
+    fn get_token<'s>(&self, _text: &'s str) -> Option<Token<'s>> {
         if let Some(s) = self.last_state {
-            if s {
+            if s && self.terminated {
                 return Some(Token::String(self.result.clone()));
             }
         }
 
-        return None;
+        None
     }
 
     fn matches(&mut self, c: &char) -> bool {
-        let mut local_state = false;
-
-        if ! self.terminated {
-            if self.result.is_empty() {
-                if *c == '"' {
-                    self.result.push(*c);
-                    local_state = true;
-                }
-            } else {
-                self.result.push(*c);
-                local_state = true;
-
-                if *c == '"' {
-                    if ! self.escaped {
-                        self.terminated = true;
-                    } else {
-                        self.escaped = false;
-                    }
-                } else if *c == '\\' {
-                    self.escaped = ! self.escaped;
-                }
-            }
-        }
+        let local_state = !self.terminated && self.push(c);
 
         if let Some(internal_state) = self.last_state {
             self.last_state = Some(internal_state && local_state);
@@ -125,86 +428,249 @@ impl TokenParser for StringParser {
             self.last_state = Some(local_state);
         }
 
-        return self.last_state.unwrap();
+        self.last_state.unwrap()
+    }
+
+    fn reset(&mut self) {
+        *self = StringParser::new();
     }
 }
 
-pub struct DiscardParser {
-    token: Vec<char>,
+const CHARACTER_NAMES: [(&str, char); 6] = [
+    ("newline", '\n'),
+    ("space", ' '),
+    ("tab", '\t'),
+    ("return", '\r'),
+    ("formfeed", '\u{c}'),
+    ("backspace", '\u{8}'),
+];
+
+#[derive(Debug,Clone)]
+pub struct CharacterParser {
+    started: bool,
     result: Vec<char>,
     last_state: Option<bool>,
 }
 
-impl DiscardParser {
-    pub fn new() -> DiscardParser {
-        DiscardParser { result: vec!(), last_state: None, token: "#_".chars().collect() }
+impl CharacterParser {
+    pub fn new() -> CharacterParser {
+        CharacterParser { started: false, result: vec!(), last_state: None }
     }
-}
 
-impl TokenParser for DiscardParser {
-    fn matches(&mut self, c: &char) -> bool {
-        if (self.last_state == None) || (self.last_state == Some(true)) {
-            if ((self.result.len() == 0) && (*c == self.token[0]))  || // first char tested is a '#'
-                ((self.result.len() == 1) && (*c == self.token[1])) || // second char tested is a '_'
-                    (self.result.len() > 1) { // we've passed the first two checks
-                    self.result.push(*c);
-                    self.last_state = Some(true);
-                    return true;
-                }
+    /// Whether `c` continues the literal rather than starting the next token.
+    pub fn wants(&self, c: &char) -> bool {
+        if ! self.started {
+            return *c == '\\';
+        }
+
+        if self.result.is_empty() {
+            return !c.is_whitespace();
+        }
+
+        let mut text: String = self.result.iter().collect();
+        text.push(*c);
+
+        if (self.result[0] == 'u') && (text.len() <= 5) && text[1..].chars().all(|h| h.is_ascii_hexdigit()) {
+            return true;
         }
 
-        self.last_state = Some(false);
-        return false;
+        CHARACTER_NAMES.iter().any(|&(name, _)| name.starts_with(&text[..]))
     }
 
-    fn get_token(&self) -> Option<Token>{
-        if Some(true) == self.last_state {
-            Some(Token::Discard(self.result.clone()))
+    /// Named characters such as `\newline` may run straight into the next token.
+    pub fn is_named(&self) -> bool {
+        let text: String = self.result.iter().collect();
+        CHARACTER_NAMES.iter().any(|&(name, _)| name == text)
+    }
+
+    pub fn error(&self) -> Option<String> {
+        if self.last_state == Some(false) || self.get_token("").is_none() {
+            let text: String = self.result.iter().collect();
+            Some(format!("unsupported character \\{}", text))
         } else {
             None
         }
     }
 }
 
+impl Default for CharacterParser {
+    fn default() -> CharacterParser {
+        CharacterParser::new()
+    }
+}
+
+impl TokenParser for CharacterParser {
+    fn matches(&mut self, c: &char) -> bool {
+        let local_state = self.wants(c);
+
+        if self.started {
+            self.result.push(*c);
+        } else {
+            self.started = local_state;
+        }
+
+        if let Some(internal_state) = self.last_state {
+            self.last_state = Some(internal_state && local_state);
+        } else {
+            self.last_state = Some(local_state);
+        }
+
+        self.last_state.unwrap()
+    }
+
+    fn get_token<'s>(&self, _text: &'s str) -> Option<Token<'s>> {
+        if (self.last_state != Some(true)) || self.result.is_empty() {
+            return None;
+        }
+
+        let text: String = self.result.iter().collect();
+
+        if self.result.len() == 1 {
+            return Some(Token::Character(self.result[0]));
+        }
+
+        if let Some(&(_, c)) = CHARACTER_NAMES.iter().find(|&&(name, _)| name == text) {
+            return Some(Token::Character(c));
+        }
+
+        if (self.result[0] == 'u') && (self.result.len() == 5) {
+            return u32::from_str_radix(&text[1..], 16).ok()
+                .and_then(char::from_u32)
+                .map(Token::Character);
+        }
+
+        None
+    }
+
+    fn reset(&mut self) {
+        self.started = false;
+        self.result.clear();
+        self.last_state = None;
+    }
+}
+
+#[derive(Debug,Clone)]
 pub struct SymbolParser {
-    result: Vec<char>,
+    first: Option<char>,
+    last: Option<char>,
+    // bytes matched so far, and the offset of the `/` among them
+    length: usize,
+    slash: Option<usize>,
+    error: Option<String>,
+    semicolons: bool,
     last_state: Option<bool>,
 }
 
 impl SymbolParser {
     pub fn new() -> SymbolParser {
-        SymbolParser { result: vec!(), last_state: None }
+        SymbolParser { first: None, last: None, length: 0, slash: None, error: None, semicolons: false, last_state: None }
+    }
+
+    /// Like `new`, but `;` is allowed anywhere `*` is, for input read without
+    /// comments.
+    pub fn with_semicolons() -> SymbolParser {
+        SymbolParser { semicolons: true, ..SymbolParser::new() }
     }
 
+    /// A `/` separates the namespace from the name, so there may be only one
+    /// and it cannot come first. The exception is `/` on its own, which is a
+    /// complete symbol.
+    ///
+    /// `#` and `:` may appear anywhere but at the start of the namespace or
+    /// of the name, so `a#`, `a:b`, `a:` and `ns/a:b` are symbols. A `:`
+    /// cannot follow another, as `::` only starts auto-resolved keywords.
+    ///
+    /// Letters are the characters Unicode calls alphabetic, so names may be
+    /// accented or written in CJK. Digits, in any script, and combining marks
+    /// such as U+0301 may follow a letter but cannot start the namespace or
+    /// the name.
+    ///
+    /// The other characters are those the EDN spec lists, `. * + ! - _ ? $ %
+    /// & = < >`, along with `;` for `with_semicolons`, which may appear
+    /// anywhere. When the namespace or the name starts with `+`, `-` or `.`, a
+    /// digit cannot follow, so `-1` is a number and `.5` and `ns/-1` are
+    /// neither. EDN has no escapes, so any other character, such as `@`, `'`
+    /// or `|`, ends the symbol or makes it invalid.
     pub fn is_character_allowed(&self, c: &char) -> bool {
-        let first_special_chars = vec!('+', '-', '.');
-        let special_chars = vec!('.', '*', '+', '!', '-', '_', '?', '$', '%', '&', '=', '<', '>', '/');
-        let extra_special_chars = vec!('#', ':');
+        let first_special_chars = ['+', '-', '.'];
+        let special_chars = ['.', '*', '+', '!', '-', '_', '?', '$', '%', '&', '=', '<', '>'];
+        let extra_special_chars = ['#', ':'];
+        let is_special = |c: &char| special_chars.contains(c) || (self.semicolons && (*c == ';'));
 
-        if self.result.is_empty() {
-            c.is_alphabetic() || special_chars.contains(c)
+        if *c == '/' {
+            return self.first.is_none() || self.slash.is_none();
+        }
+
+        if (*c == ':') && (self.last == Some(':')) {
+            return false;
+        }
+
+        // whether the namespace or the name so far is just a `+`, `-` or `.`
+        let after_sign = self.last.is_some_and(|last| first_special_chars.contains(&last)) &&
+            ((self.length == 1) || (self.slash.map(|slash| slash + 2) == Some(self.length)));
+
+        match self.first {
+            None => c.is_alphabetic() || is_special(c),
+            Some('/') => false,
+            _ if after_sign => c.is_alphabetic() || is_special(c) || extra_special_chars.contains(c),
+            _ if self.last == Some('/') => c.is_alphabetic() || is_special(c),
+            _ => c.is_alphanumeric() || is_combining_mark(*c) || is_special(c) || extra_special_chars.contains(c),
+        }
+    }
+}
+
+// the blocks of combining diacritical marks, which decorate the character
+// before them
+fn is_combining_mark(c: char) -> bool {
+    matches!(c, '\u{300}'..='\u{36f}' | '\u{1ab0}'..='\u{1aff}' | '\u{1dc0}'..='\u{1dff}' | '\u{20d0}'..='\u{20ff}' | '\u{fe20}'..='\u{fe2f}')
+}
+
+
+impl SymbolParser {
+    pub fn started(&self) -> bool {
+        self.last_state.is_some()
+    }
+
+    /// Describes a misplaced `/` or `::`, the mistakes that make an otherwise
+    /// well-formed symbol invalid.
+    pub fn error(&self) -> Option<String> {
+        if self.error.is_some() {
+            self.error.clone()
+        } else if (self.last_state == Some(true)) && (self.length > 1) && (self.last == Some('/')) {
+            Some(String::from("trailing `/`"))
         } else {
-            if *self.result.first().unwrap() == '/' {
-                false
-            } else if (self.result.len() == 1) && first_special_chars.contains(self.result.first().unwrap()) {
-                c.is_alphabetic() || special_chars.contains(c) || extra_special_chars.contains(c)
-            } else if *self.result.last().unwrap() == '/' {
-                c.is_alphabetic() || special_chars.contains(c)
-            } else {
-                c.is_alphanumeric() || special_chars.contains(c) || extra_special_chars.contains(c)
-            }
+            None
         }
     }
 }
 
+impl Default for SymbolParser {
+    fn default() -> SymbolParser {
+        SymbolParser::new()
+    }
+}
 
 impl TokenParser for SymbolParser {
     fn matches(&mut self, c: &char) -> bool {
         let mut local_state = false;
 
         if self.is_character_allowed(c) {
-            self.result.push(*c);
+            if *c == '/' {
+                self.slash = Some(self.length);
+            }
+
+            self.first = self.first.or(Some(*c));
+            self.last = Some(*c);
+            self.length += c.len_utf8();
             local_state = true;
+        } else if self.last_state != Some(false) {
+            if self.first == Some('/') {
+                self.error = Some(String::from("leading `/`"));
+            } else if *c == '/' {
+                self.error = Some(String::from("more than one `/`"));
+            } else if (*c == ':') && (self.last == Some(':')) {
+                self.error = Some(String::from("`::` after the start"));
+            }
         }
 
         if let Some(internal_state) = self.last_state {
@@ -213,218 +679,2608 @@ impl TokenParser for SymbolParser {
             self.last_state = Some(local_state);
         }
 
-        return self.last_state.unwrap();
+        self.last_state.unwrap()
     }
 
-    fn get_token(&self) -> Option<Token> {
+    fn get_token<'s>(&self, text: &'s str) -> Option<Token<'s>> {
         if let Some(valid) = self.last_state {
-            if valid && (*self.result.last().unwrap() != '/') {
-                return Some(Token::Symbol(self.result.clone()));
+            if valid && (self.length == 1) {
+                return Some(Token::Symbol { namespace: None, name: text });
+            }
+
+            if valid && (self.last != Some('/')) {
+                return Some(match self.slash {
+                    Some(slash) => Token::Symbol { namespace: Some(&text[..slash]), name: &text[slash + 1..] },
+                    None => Token::Symbol { namespace: None, name: text },
+                });
             }
         }
 
-        return None;
+        None
+    }
+
+    fn reset(&mut self) {
+        *self = SymbolParser { semicolons: self.semicolons, ..SymbolParser::new() };
     }
 }
 
-impl<'a> Parser<'a> {
-    fn is_whitespace(ch: &char) -> bool {
-        ch.is_whitespace() || (*ch == ',')
+#[derive(Debug,Clone)]
+pub struct KeywordParser {
+    // leading `:`s seen, two for an auto-resolved keyword
+    colons: usize,
+    symbol: SymbolParser,
+    last_state: Option<bool>,
+}
+
+impl KeywordParser {
+    pub fn new() -> KeywordParser {
+        KeywordParser { colons: 0, symbol: SymbolParser::new(), last_state: None }
     }
 
-    fn new(source: &'a String) -> Parser<'a> {
-        Parser { 
-            source: source,
-            iterator: source.chars(),
-            current_character: None,
-            character: 0,
-            line: 1
-        }
+    /// Like `new`, with names read as by `SymbolParser::with_semicolons`.
+    pub fn with_semicolons() -> KeywordParser {
+        KeywordParser { symbol: SymbolParser::with_semicolons(), ..KeywordParser::new() }
     }
+}
 
-    fn next_character(&mut self) -> Option<char> {
-        let ch_opt = self.iterator.next();
+impl KeywordParser {
+    /// Describes the mistakes `SymbolParser::error` does, and a name of `/`,
+    /// which a symbol may have but a keyword may not.
+    pub fn error(&self) -> Option<String> {
+        let symbol = &self.symbol;
 
-        match ch_opt {
-            Some(_) => {
-                self.character += 1;
-            },
-            _ => {}
+        if (self.last_state == Some(true)) && (symbol.length == 1) && (symbol.first == Some('/')) {
+            Some(String::from("`/` is not a keyword name"))
+        } else {
+            symbol.error()
         }
-
-        self.current_character = ch_opt;
-        self.current_character
     }
+}
 
-    fn parse_whitespace(&mut self) -> Option<Token> {
-        let mut ws: Vec<char> = vec!();
+impl Default for KeywordParser {
+    fn default() -> KeywordParser {
+        KeywordParser::new()
+    }
+}
 
-        while let Some(c) = self.current_character {
-            if Parser::is_whitespace(&c) {
-                if c == '\n' {
-                    self.line += 1;
-                    self.character = 0;
-                }
-                ws.push(c);
-                self.next_character();
-            } else {
-                break;
-            }
-        }
+impl TokenParser for KeywordParser {
+    fn matches(&mut self, c: &char) -> bool {
+        let local_state = match self.colons {
+            0 if *c == ':' => {
+                self.colons = 1;
+                true
+            },
+            0 => false,
+            1 if (*c == ':') && !self.symbol.started() => {
+                self.colons = 2;
+                true
+            },
+            _ => self.symbol.matches(c),
+        };
 
-        if !ws.is_empty() {
-            Some(Token::Whitespace(ws))
+        if let Some(internal_state) = self.last_state {
+            self.last_state = Some(internal_state && local_state);
         } else {
-            None
+            self.last_state = Some(local_state);
         }
+
+        self.last_state.unwrap()
     }
 
-    pub fn parse_value(&mut self) -> Option<Token> {
-        let mut nil_parser  = KeywordTokenParser::new("nil", Token::Nil);
-        let mut true_parser = KeywordTokenParser::new("true", Token::Boolean(true));
-        let mut false_parser = KeywordTokenParser::new("false", Token::Boolean(false));
-        let mut symbol_parser = SymbolParser::new();
-        let mut string_parser = StringParser::new();
-        let mut discard_parser = DiscardParser::new();
-
-        let mut value_parsers = vec![
-            &mut nil_parser as &mut TokenParser,
-            &mut true_parser,
-            &mut false_parser,
-            &mut symbol_parser,
-            &mut string_parser,
-            &mut discard_parser,
-            ];
-
-        while let Some(ch) = self.next_character() {
-            if ! Parser::is_whitespace(&ch) {
-                for p in value_parsers.iter_mut() {
-                    p.matches(&ch);
-                }
-            } else {
-                break;
-            }
+    fn get_token<'s>(&self, text: &'s str) -> Option<Token<'s>> {
+        if self.last_state != Some(true) {
+            return None;
         }
 
-        let ws = self.parse_whitespace();
-        let tokens = value_parsers.iter().map(|p| p.get_token());
-
-        for t in tokens {
-            if let Some(_) = t {
-                return t;
-            }
+        // past the leading `:` or `::`; `/` on its own is a symbol but `:/`
+        // is not a keyword
+        match self.symbol.get_token(&text[self.colons..]) {
+            Some(Token::Symbol { name: "/", .. }) => None,
+            Some(Token::Symbol { namespace, name }) => Some(Token::Keyword {
+                namespace: namespace.map(String::from),
+                name: String::from(name),
+                auto_resolved: self.colons == 2,
+            }),
+            _ => None,
         }
+    }
 
-        return None;
+    fn reset(&mut self) {
+        self.colons = 0;
+        self.symbol.reset();
+        self.last_state = None;
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use super::TokenParser;
+// Numeric parsers keep only what they have to know about the characters seen
+// so far; `get_token` reads the literal itself back out of `text`. Every
+// character they accept is ASCII, so counts of characters are byte offsets.
+#[derive(Debug,Clone)]
+pub struct IntegerTokenParser {
+    length: usize,
+    signed: bool,
+    last: Option<char>,
+    // offset in the literal of the `x` or `r` that ends a radix prefix
+    prefix: Option<usize>,
+    strict: bool,
+    last_state: Option<bool>,
+}
 
-    #[test]
-    fn initialization_test() {
-        let source = String::from("");
-        let p = Parser::new(&source);
-        assert_eq!(source, *p.source);
-        assert_eq!(0, p.character);
-        assert_eq!(1, p.line);
+impl IntegerTokenParser {
+    pub fn new() -> IntegerTokenParser {
+        IntegerTokenParser { length: 0, signed: false, last: None, prefix: None, strict: false, last_state: None }
     }
 
-    #[test]
-    fn next_character_test() {
-        let source = String::from("str");
-        let mut p = Parser::new(&source);
+    /// Like `new`, but leading zeros make the literal invalid.
+    pub fn strict() -> IntegerTokenParser {
+        IntegerTokenParser { strict: true, ..IntegerTokenParser::new() }
+    }
 
-        let ch_opt = p.next_character();
-        assert_eq!('s', ch_opt.unwrap());
-        assert_eq!(1, p.character);
+    // the number of characters read so far, without the sign
+    fn digit_count(&self) -> usize {
+        self.length - (self.signed as usize)
+    }
 
-        let ch_opt = p.next_character();
-        assert_eq!('t', ch_opt.unwrap());
-        assert_eq!(2, p.character);
+    // the digits of `text`, without the sign
+    fn digits<'s>(&self, text: &'s str) -> &'s str {
+        &text[(self.signed as usize)..self.length]
+    }
 
-        let ch_opt = p.next_character();
-        assert_eq!('r', ch_opt.unwrap());
-        assert_eq!(3, p.character);
+    /// Besides decimal, accepts `0x` hexadecimal, octal with a leading `0`
+    /// and `NrDIGITS` in any radix from 2 to 36. Digits after a prefix are
+    /// only checked against the radix once the literal ends.
+    pub fn is_character_allowed(&self, c: &char) -> bool {
+        let last = match self.last {
+            Some(last) => last,
+            None => return c.is_ascii_digit() || (*c == '+') || (*c == '-'),
+        };
+
+        if self.prefix.is_some() {
+            c.is_ascii_alphanumeric()
+        } else if (*c == 'x') || (*c == 'X') {
+            (self.digit_count() == 1) && (last == '0')
+        } else if (*c == 'r') || (*c == 'R') {
+            (1..=2).contains(&self.digit_count())
+        } else if last == 'N' {
+            // the arbitrary precision suffix ends the literal
+            false
+        } else if *c == 'N' {
+            last.is_ascii_digit()
+        } else {
+            c.is_ascii_digit()
+        }
+    }
+}
 
-        let ch_opt = p.next_character();
-        assert_eq!(None, ch_opt);
-        assert_eq!(3, p.character);
+impl IntegerTokenParser {
+    fn leading_zero(&self, text: &str) -> bool {
+        let digits = self.digits(text).as_bytes();
+        (digits.len() > 1) && (digits[0] == b'0') && digits[1].is_ascii_digit()
     }
 
-    #[test]
-    fn nil_token_parser_test() {
-        let mut parser = KeywordTokenParser::new("nil", Token::Nil);
+    // the signed digits and radix of a well-formed literal, and whether it
+    // has the `N` suffix; without it the literal may still be too large for
+    // an `i64`
+    fn literal(&self, text: &str) -> Option<(String, u32, bool)> {
+        if self.last_state != Some(true) {
+            return None;
+        }
 
-        // Matches up to 'nil'
-        assert!(parser.matches(&'n'));
-        assert!(parser.matches(&'i'));
-        assert!(parser.matches(&'l'));
+        let sign = if text.starts_with('-') { "-" } else { "" };
 
-        assert_eq!(Some(Token::Nil), parser.get_token());
+        let (digits, radix) = match self.prefix {
+            Some(prefix) => {
+                let radix = match &text[prefix..=prefix] {
+                    "x" | "X" => 16,
+                    _ => text[(self.signed as usize)..prefix].parse().ok().filter(|radix| (2..=36).contains(radix))?,
+                };
 
-        // Failes to match beyond 'nil'
-        assert!(!parser.matches(&'l'));
-        assert_eq!(None, parser.get_token());
+                (&text[prefix + 1..self.length], radix)
+            },
+            None if self.leading_zero(text) && self.strict => return None,
+            None if self.leading_zero(text) => (self.digits(text), 8),
+            None => (self.digits(text), 10),
+        };
+
+        // in a radix of 24 or more, a final `N` is a digit
+        let big = digits.ends_with('N') && !'N'.is_digit(radix);
+        let digits = if big { &digits[..digits.len() - 1] } else { digits };
+
+        // a lone sign is a symbol, not an integer
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return None;
+        }
+
+        Some((format!("{}{}", sign, digits), radix, big))
     }
 
-    #[test]
-    fn whitespace_test() {
-        assert!(Parser::is_whitespace(&' '));
-        assert!(Parser::is_whitespace(&'\t'));
-        assert!(Parser::is_whitespace(&','));
-        assert!(!Parser::is_whitespace(&'f'));
+    /// Explains why a well-formed literal `text` gives no token, which
+    /// happens only when it does not fit in an `i64`.
+    pub fn error(&self, text: &str) -> Option<String> {
+        let (digits, radix, _) = self.literal(text).filter(|&(_, _, big)| !big)?;
+        i64::from_str_radix(&digits, radix).err().map(|_| String::from("out of range"))
     }
+}
 
-    #[test]
-    fn parse_whitespace_test() {
-        let s = " ";
-        let string = &String::from(s);
-        let mut p = Parser::new(string);
-        p.next_character();
-        assert_eq!(Some(Token::Whitespace(s.chars().collect())), p.parse_whitespace());
+// `digits` in `radix`, after any sign, written out in decimal
+fn to_decimal(digits: &str, radix: u32) -> String {
+    let (sign, digits) = match digits.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", digits),
+    };
 
-        let s = " \n ";
-        let string = &String::from(s);
-        let mut p = Parser::new(string);
-        p.next_character();
-        assert_eq!(Some(Token::Whitespace(s.chars().collect())), p.parse_whitespace());
+    // the decimal digits of the value so far, lowest first
+    let mut decimal = vec!(0);
+
+    for c in digits.chars() {
+        let mut carry = c.to_digit(radix).unwrap();
+
+        for d in decimal.iter_mut() {
+            let n = (*d * radix) + carry;
+            *d = n % 10;
+            carry = n / 10;
+        }
+
+        while carry > 0 {
+            decimal.push(carry % 10);
+            carry /= 10;
+        }
+    }
+
+    let mut result = String::from(sign);
+    result.extend(decimal.iter().rev().map(|&d| char::from_digit(d, 10).unwrap()));
+    result
+}
+
+impl Default for IntegerTokenParser {
+    fn default() -> IntegerTokenParser {
+        IntegerTokenParser::new()
+    }
+}
+
+impl TokenParser for IntegerTokenParser {
+    fn matches(&mut self, c: &char) -> bool {
+        let mut local_state = false;
+
+        if self.is_character_allowed(c) {
+            if self.prefix.is_none() && matches!(*c, 'x' | 'X' | 'r' | 'R') {
+                self.prefix = Some(self.length);
+            }
+
+            if self.last.is_none() && ((*c == '+') || (*c == '-')) {
+                self.signed = true;
+            }
+
+            self.last = Some(*c);
+            self.length += 1;
+            local_state = true;
+        }
+
+        if let Some(internal_state) = self.last_state {
+            self.last_state = Some(internal_state && local_state);
+        } else {
+            self.last_state = Some(local_state);
+        }
+
+        self.last_state.unwrap()
+    }
+
+    fn get_token<'s>(&self, text: &'s str) -> Option<Token<'s>> {
+        if self.last_state != Some(true) {
+            return None;
+        }
+
+        match self.literal(text)? {
+            (digits, 10, true) => Some(Token::BigInteger(digits)),
+            (digits, radix, true) => Some(Token::BigInteger(to_decimal(&digits, radix))),
+            (digits, radix, false) => i64::from_str_radix(&digits, radix).ok().map(Token::Integer),
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = IntegerTokenParser { strict: self.strict, ..IntegerTokenParser::new() };
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct FloatTokenParser {
+    length: usize,
+    last: Option<char>,
+    fraction: bool,
+    exponent: bool,
+    last_state: Option<bool>,
+}
+
+impl FloatTokenParser {
+    pub fn new() -> FloatTokenParser {
+        FloatTokenParser { length: 0, last: None, fraction: false, exponent: false, last_state: None }
+    }
+
+    pub fn is_character_allowed(&self, c: &char) -> bool {
+        let last = match self.last {
+            Some(last) => last,
+            None => return c.is_ascii_digit() || (*c == '+') || (*c == '-'),
+        };
+
+        // the exact precision suffix ends the literal
+        if last == 'M' {
+            return false;
+        }
+
+        match *c {
+            '0'..='9' => true,
+            '.' => last.is_ascii_digit() && !self.fraction && !self.exponent,
+            'e' | 'E' => (last.is_ascii_digit() || (last == '.')) && !self.exponent,
+            '+' | '-' => (last == 'e') || (last == 'E'),
+            'M' => last.is_ascii_digit() || (last == '.'),
+            _ => false,
+        }
+    }
+}
+
+impl Default for FloatTokenParser {
+    fn default() -> FloatTokenParser {
+        FloatTokenParser::new()
+    }
+}
+
+impl TokenParser for FloatTokenParser {
+    fn matches(&mut self, c: &char) -> bool {
+        let mut local_state = false;
+
+        if self.is_character_allowed(c) {
+            match *c {
+                '.' => self.fraction = true,
+                'e' | 'E' => self.exponent = true,
+                _ => (),
+            }
+
+            self.last = Some(*c);
+            self.length += 1;
+            local_state = true;
+        }
+
+        if let Some(internal_state) = self.last_state {
+            self.last_state = Some(internal_state && local_state);
+        } else {
+            self.last_state = Some(local_state);
+        }
+
+        self.last_state.unwrap()
+    }
+
+    fn get_token<'s>(&self, text: &'s str) -> Option<Token<'s>> {
+        if let (Some(true), Some(last)) = (self.last_state, self.last) {
+            if last == 'M' {
+                let digits = text[..self.length - 1].trim_start_matches('+');
+                return Some(Token::BigDecimal(String::from(digits)));
+            }
+
+            // without a fraction or an exponent the literal is an integer
+            if (last.is_ascii_digit() || (last == '.')) && (self.fraction || self.exponent) {
+                return text[..self.length].parse().ok().map(Token::Float);
+            }
+        }
+
+        None
+    }
+
+    fn reset(&mut self) {
+        *self = FloatTokenParser::new();
+    }
+}
+
+/// Reads Clojure's ratios, such as `22/7`, in lowest terms. One that
+/// reduces to a whole number, such as `6/3`, is an integer.
+#[derive(Debug,Clone)]
+pub struct RatioParser {
+    length: usize,
+    last: Option<char>,
+    // offset in the literal of the `/`
+    slash: Option<usize>,
+    last_state: Option<bool>,
+}
+
+impl RatioParser {
+    pub fn new() -> RatioParser {
+        RatioParser { length: 0, last: None, slash: None, last_state: None }
+    }
+
+    pub fn is_character_allowed(&self, c: &char) -> bool {
+        match (self.last, *c) {
+            (None, c) => c.is_ascii_digit() || (c == '+') || (c == '-'),
+            (Some(last), '/') => self.slash.is_none() && last.is_ascii_digit(),
+            (Some(_), c) => c.is_ascii_digit(),
+        }
+    }
+
+    // the numerator and denominator of a well-formed literal, which may
+    // still be out of range or divide by zero
+    fn parts(&self, text: &str) -> Option<Result<(i64, i64), String>> {
+        let slash = self.slash?;
+
+        if (self.last_state != Some(true)) || (self.length == slash + 1) {
+            return None;
+        }
+
+        let numerator = text[..slash].trim_start_matches('+').parse::<i64>();
+        let denominator = text[slash + 1..self.length].parse::<i64>();
+
+        Some(match (numerator, denominator) {
+            (Ok(_), Ok(0)) => Err(String::from("has a zero denominator")),
+            (Ok(numerator), Ok(denominator)) => Ok((numerator, denominator)),
+            _ => Err(String::from("out of range")),
+        })
+    }
+
+    /// Explains why a well-formed ratio `text` gives no token.
+    pub fn error(&self, text: &str) -> Option<String> {
+        self.parts(text)?.err()
+    }
+}
+
+impl Default for RatioParser {
+    fn default() -> RatioParser {
+        RatioParser::new()
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let remainder = a % b;
+        a = b;
+        b = remainder;
+    }
+
+    a
+}
+
+impl TokenParser for RatioParser {
+    fn matches(&mut self, c: &char) -> bool {
+        let mut local_state = false;
+
+        if self.is_character_allowed(c) {
+            if *c == '/' {
+                self.slash = Some(self.length);
+            }
+
+            self.last = Some(*c);
+            self.length += 1;
+            local_state = true;
+        }
+
+        if let Some(internal_state) = self.last_state {
+            self.last_state = Some(internal_state && local_state);
+        } else {
+            self.last_state = Some(local_state);
+        }
+
+        self.last_state.unwrap()
+    }
+
+    fn get_token<'s>(&self, text: &'s str) -> Option<Token<'s>> {
+        let (numerator, denominator) = self.parts(text)?.ok()?;
+
+        // the denominator is positive, so this fits in an `i64`
+        let divisor = gcd(numerator.unsigned_abs(), denominator as u64) as i64;
+        let (numerator, denominator) = (numerator / divisor, denominator / divisor);
+
+        if denominator == 1 {
+            Some(Token::Integer(numerator))
+        } else {
+            Some(Token::Ratio { numerator, denominator })
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = RatioParser::new();
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn is_whitespace(ch: &char) -> bool {
+        ch.is_whitespace() || (*ch == ',')
+    }
+
+    // characters that end a scalar without being part of it
+    fn is_terminator(&self, ch: &char) -> bool {
+        Parser::is_whitespace(ch) || (*ch == '"') || ((*ch == ';') && self.options.allow_comments) ||
+            Delimiter::opened_by(ch).is_some() || Delimiter::closed_by(ch).is_some()
+    }
+
+    pub fn new(source: &'a str) -> Parser<'a> {
+        Parser::with_options(source, ParserOptions::default())
+    }
+
+    pub fn with_options(source: &'a str, options: ParserOptions) -> Parser<'a> {
+        Parser {
+            source,
+            iterator: source.chars(),
+            current_character: None,
+            character: 0,
+            line: 1,
+            offset: 0,
+            tokens: 0,
+            token_end: 0,
+            failed: false,
+            scalar_parsers: ScalarParsers::new(&options),
+            options,
+        }
+    }
+
+    /// Adds a recognizer for scalars of a kind of its own, which is tried
+    /// before the built-in ones.
+    pub fn add_token_parser<P>(&mut self, parser: P) where P: TokenParser + 'static {
+        self.scalar_parsers.custom.push(Box::new(parser));
+    }
+
+    fn next_character(&mut self) -> Option<char> {
+        let previous = self.current_character;
+
+        if let Some(c) = previous {
+            self.offset += c.len_utf8();
+        }
+
+        let ch_opt = self.iterator.next();
+
+        // `\r\n` and a lone `\r` each end a line, as `\n` does
+        let line_break = match previous {
+            Some('\n') => true,
+            Some('\r') => ch_opt != Some('\n'),
+            _ => false,
+        };
+
+        if line_break {
+            self.line += 1;
+            self.character = 0;
+        }
+
+        // `character` is the column the current character starts at
+        if ch_opt.is_some() {
+            self.character += match previous {
+                Some(c) if !line_break => self.options.columns.width(c),
+                _ => 1,
+            };
+        }
+
+        self.current_character = ch_opt;
+        self.current_character
+    }
+
+    /// Rewinds to the start of the source, keeping the options, so that it
+    /// can be read again.
+    pub fn reset(&mut self) {
+        self.iterator = self.source.chars();
+        self.current_character = None;
+        self.character = 0;
+        self.line = 1;
+        self.offset = 0;
+        self.tokens = 0;
+        self.token_end = 0;
+        self.failed = false;
+    }
+
+    /// The 1-based `(line, character)` of the next character to be read. It
+    /// is `(1, 0)` before any reading, and stays on the last character once
+    /// the input is exhausted.
+    pub fn position(&self) -> (i64, i64) {
+        (self.line, self.character)
+    }
+
+    /// The byte offset into the source of the next character to be read.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+
+    // the character after `current_character`, without moving past either
+    fn peek_character(&self) -> Option<char> {
+        self.iterator.clone().next()
+    }
+
+    fn parse_whitespace(&mut self) -> Option<Token<'a>> {
+        let start = self.offset;
+
+        while let Some(c) = self.current_character {
+            if Parser::is_whitespace(&c) && !((c == ',') && self.options.comma_tokens) {
+                self.next_character();
+            } else {
+                break;
+            }
+        }
+
+        if self.offset > start {
+            Some(Token::Whitespace(&self.source[start..self.offset]))
+        } else {
+            None
+        }
+    }
+
+    fn parse_comma(&mut self) -> Option<Token<'a>> {
+        if self.current_character != Some(',') {
+            return None;
+        }
+
+        self.next_character();
+        Some(Token::Comma)
+    }
+
+    fn parse_comment(&mut self) -> Option<Token<'a>> {
+        if !self.options.allow_comments || (self.current_character != Some(';')) {
+            return None;
+        }
+
+        let mut comment = String::new();
+
+        while let Some(c) = self.current_character {
+            if (c == '\n') || (c == '\r') {
+                break;
+            }
+
+            comment.push(c);
+            self.next_character();
+        }
+
+        Some(Token::Comment(comment))
+    }
+
+    fn parse_string(&mut self) -> Result<Token<'a>, String> {
+        let mut string_parser = StringParser::new();
+
+        while let Some(ch) = self.current_character {
+            string_parser.matches(&ch);
+            self.next_character();
+
+            if string_parser.is_terminated() {
+                break;
+            }
+        }
+
+        match string_parser.error() {
+            Some(error) => Err(error),
+            None => string_parser.get_token("").ok_or_else(|| String::from("invalid string")),
+        }
+    }
+
+    // a symbol following `prefix` characters of dispatch, along with the
+    // text of the whole token
+    fn parse_prefixed_symbol(&mut self, prefix: usize) -> (&'a str, Option<Token<'a>>) {
+        let mut symbol_parser = if self.options.allow_comments { SymbolParser::new() } else { SymbolParser::with_semicolons() };
+        let start = self.offset;
+
+        for _ in 0..prefix {
+            self.next_character();
+        }
+
+        while let Some(ch) = self.current_character {
+            if self.is_terminator(&ch) {
+                break;
+            }
+
+            symbol_parser.matches(&ch);
+            self.next_character();
+        }
+
+        let text = &self.source[start..self.offset];
+        (text, symbol_parser.get_token(&text[prefix..]))
+    }
+
+    fn parse_tag(&mut self) -> Result<Token<'a>, String> {
+        match self.parse_prefixed_symbol(1) {
+            (text, Some(Token::Symbol { .. })) => Ok(Token::Tag(String::from(&text[1..]))),
+            (text, _) => Err(format!("invalid tag `{}`", text)),
+        }
+    }
+
+    // the namespace must itself be unqualified
+    fn parse_map_namespace(&mut self) -> Result<Token<'a>, String> {
+        match self.parse_prefixed_symbol(2) {
+            (_, Some(Token::Symbol { namespace: None, name })) if name != "/" => Ok(Token::NamespacedMap(String::from(name))),
+            (text, _) => Err(format!("invalid map namespace `{}`", text)),
+        }
+    }
+
+    // the floats that have no digits, `##Inf`, `##-Inf` and `##NaN`
+    fn parse_symbolic_value(&mut self) -> Result<Token<'a>, String> {
+        match self.parse_prefixed_symbol(2) {
+            (_, Some(Token::Symbol { namespace: None, name: "Inf" })) => Ok(Token::Float(f64::INFINITY)),
+            (_, Some(Token::Symbol { namespace: None, name: "-Inf" })) => Ok(Token::Float(f64::NEG_INFINITY)),
+            (_, Some(Token::Symbol { namespace: None, name: "NaN" })) => Ok(Token::Float(f64::NAN)),
+            (text, _) => Err(format!("unknown symbolic value `{}`", text)),
+        }
+    }
+
+    // The `#` dispatch character, where the character after it picks the
+    // form: `#{` sets, `#_` discards, `#:ns` namespaced maps, `##` symbolic
+    // values and `#inst`, `#uuid` or any other symbol a tag.
+    fn parse_dispatch(&mut self) -> Result<Token<'a>, String> {
+        match self.peek_character() {
+            Some('{') => Ok(self.parse_pair(Token::OpenSet)),
+            Some('_') => Ok(self.parse_pair(Token::Discard)),
+            Some(':') => self.parse_map_namespace(),
+            Some('#') => self.parse_symbolic_value(),
+            Some(c) if c.is_alphabetic() => self.parse_tag(),
+            Some(c) if !Parser::is_whitespace(&c) => {
+                self.next_character();
+                self.next_character();
+                Err(format!("unsupported reader macro `#{}`", c))
+            },
+            _ => {
+                self.next_character();
+                Err(String::from("unsupported reader macro `#`"))
+            },
+        }
+    }
+
+    // two-character dispatch tokens such as `#{` and `#_`
+    fn parse_pair(&mut self, token: Token<'a>) -> Token<'a> {
+        self.next_character();
+        self.next_character();
+        token
+    }
+
+    fn parse_character(&mut self) -> Result<Token<'a>, String> {
+        let mut character_parser = CharacterParser::new();
+
+        while let Some(ch) = self.current_character {
+            if !character_parser.wants(&ch) {
+                // `\ab` is not `\a` followed by `b`
+                if !self.is_terminator(&ch) && !character_parser.is_named() {
+                    character_parser.matches(&ch);
+                }
+
+                break;
+            }
+
+            character_parser.matches(&ch);
+            self.next_character();
+        }
+
+        match character_parser.error() {
+            Some(error) => Err(error),
+            None => character_parser.get_token("").ok_or_else(|| String::from("invalid character literal")),
+        }
+    }
+
+    fn parse_scalar(&mut self) -> Result<Token<'a>, String> {
+        for p in self.scalar_parsers.all() {
+            p.reset();
+        }
+
+        let start = self.offset;
+
+        while let Some(ch) = self.current_character {
+            if self.is_terminator(&ch) {
+                break;
+            }
+
+            for p in self.scalar_parsers.all() {
+                p.matches(&ch);
+            }
+
+            self.next_character();
+        }
+
+        // scalars are contiguous, so their text can be borrowed from the source
+        let text = &self.source[start..self.offset];
+
+        if let Some(token) = self.scalar_parsers.all().find_map(|p| p.get_token(text)) {
+            return Ok(token);
+        }
+
+        let parsers = &self.scalar_parsers;
+
+        match (parsers.integer.error(text), parsers.ratio.error(text), parsers.symbol.error(), parsers.keyword.error()) {
+            (Some(error), _, _, _) => Err(format!("integer literal `{}` {}", text, error)),
+            (_, Some(error), _, _) => Err(format!("ratio `{}` {}", text, error)),
+            (_, _, Some(error), _) => Err(format!("invalid symbol `{}`: {}", text, error)),
+            (_, _, _, Some(error)) => Err(format!("invalid keyword `{}`: {}", text, error)),
+            _ => Err(format!("invalid token `{}`", text)),
+        }
+    }
+
+    // Skips a run of ASCII whitespace and commas by scanning the source
+    // bytes, counting lines and columns as `next_character` would. Every one
+    // of them is a single column wide in any `ColumnMode`. Other whitespace
+    // is left to `parse_whitespace`.
+    fn skip_ascii_whitespace(&mut self) -> bool {
+        let bytes = self.source.as_bytes();
+        let mut offset = self.offset;
+
+        while let Some(&b) = bytes.get(offset) {
+            if !b.is_ascii() || !Parser::is_whitespace(&(b as char)) {
+                break;
+            }
+
+            let next = bytes.get(offset + 1);
+
+            if (b == b'\n') || ((b == b'\r') && (next != Some(&b'\n'))) {
+                self.line += 1;
+                self.character = 0;
+            }
+
+            if next.is_some() {
+                self.character += 1;
+            }
+
+            offset += 1;
+        }
+
+        if offset == self.offset {
+            return false;
+        }
+
+        let mut rest = self.source[offset..].chars();
+        self.current_character = rest.next();
+        self.iterator = rest;
+        self.offset = offset;
+        true
+    }
+
+    // whitespace is not kept here, so it bypasses `parse_whitespace` where it
+    // can; `next_token` returns it and so still reads it a character at a time
+    fn skip_whitespace(&mut self) {
+        // nothing has been read yet; at the end of input this is a no-op
+        if self.current_character.is_none() {
+            self.next_character();
+        }
+
+        while self.skip_ascii_whitespace() ||
+            self.parse_whitespace().or_else(|| self.parse_comma()).or_else(|| self.parse_comment()).is_some() {}
+    }
+
+    /// Reads the next token, or `Ok(None)` at the end of the input, which
+    /// input holding only whitespace and comments is already at. Errors are
+    /// reported at the position the offending token starts.
+    pub fn parse_value(&mut self) -> Result<Option<Token<'a>>, ParseError> {
+        self.skip_whitespace();
+
+        let token = self.parse_token()?;
+
+        self.skip_whitespace();
+        Ok(token)
+    }
+
+    /// Skips whitespace and comments, then fails with "unexpected trailing
+    /// content" at the token that follows, if there is one. This is how
+    /// `parse` checks that nothing comes after its value.
+    pub fn expect_eof(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+
+        match self.current_character {
+            Some(_) => Err(self.error(String::from("unexpected trailing content"))),
+            None => Ok(()),
+        }
+    }
+
+    /// Like `parse_value`, but whitespace and comments are returned as tokens
+    /// of their own rather than skipped.
+    pub fn next_token(&mut self) -> Result<Option<Token<'a>>, ParseError> {
+        if self.current_character.is_none() {
+            self.next_character();
+        }
+
+        if let Some(token) = self.parse_whitespace().or_else(|| self.parse_comma()).or_else(|| self.parse_comment()) {
+            return Ok(Some(token));
+        }
+
+        self.parse_token()
+    }
+
+    /// Like `next_token`, with each token paired with its location.
+    pub fn next_spanned(&mut self) -> Result<Option<(Token<'a>, Span)>, ParseError> {
+        if self.current_character.is_none() {
+            self.next_character();
+        }
+
+        let (start, line, character) = (self.offset, self.line, self.character);
+
+        Ok(self.next_token()?.map(|token| (token, Span { start, end: self.offset, line, character })))
+    }
+
+    // the token starting at the current character, which is not whitespace
+    fn parse_token(&mut self) -> Result<Option<Token<'a>>, ParseError> {
+        let (start, line, character) = (self.offset, self.line, self.character);
+
+        if self.current_character.is_some() {
+            if self.tokens >= self.options.max_total_tokens {
+                return Err(self.error(format!("input exceeds {} tokens", self.options.max_total_tokens)));
+            }
+
+            self.tokens += 1;
+        }
+
+        let token = match self.current_character {
+            Some('"') => self.parse_string(),
+            Some('\\') => self.parse_character(),
+            Some('#') => self.parse_dispatch(),
+            Some(c) => {
+                if let Some(delimiter) = Delimiter::opened_by(&c) {
+                    self.next_character();
+                    Ok(Token::Open(delimiter))
+                } else if let Some(delimiter) = Delimiter::closed_by(&c) {
+                    self.next_character();
+                    Ok(Token::Close(delimiter))
+                } else {
+                    self.parse_scalar()
+                }
+            },
+            None => return Ok(None),
+        };
+
+        self.token_end = self.offset;
+        token.map(Some).map_err(|message| {
+            ParseError::new(message, line, character).with_span(Span { start, end: self.offset, line, character })
+        })
+    }
+
+    // an error at the current character
+    pub(crate) fn error(&self, message: String) -> ParseError {
+        let (start, line, character) = (self.offset, self.line, self.character);
+        ParseError::new(message, line, character).with_span(Span { start, end: start, line, character })
+    }
+}
+
+/// Yields every token in the input, whitespace and comments included, ending
+/// at the end of the input or after the first error, which is returned as the
+/// last item.
+impl<'a> Iterator for Parser<'a> {
+    type Item = Result<Token<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Result<Token<'a>, ParseError>> {
+        if self.failed {
+            return None;
+        }
+
+        let result = self.next_token();
+        self.failed = result.is_err();
+        result.transpose()
+    }
+}
+
+/// Parses exactly one EDN value from `input`.
+///
+/// Whitespace and comments around the value are consumed; anything else after
+/// it is an error, as is input that holds no value at all.
+pub fn parse(input: &str) -> Result<Value, ParseError> {
+    parse_with_options(input, &ReaderOptions::default())
+}
+
+/// Like `parse`, but with tagged literals read using the handlers registered
+/// in `options`.
+pub fn parse_with_options(input: &str, options: &ReaderOptions) -> Result<Value, ParseError> {
+    let mut reader = Reader::new(Parser::with_options(input, options.parser.clone()), options);
+
+    let value = match reader.read()? {
+        Some(value) => value,
+        None => {
+            let parser = reader.parser();
+            return Err(parser.error(String::from("unexpected end of input")));
+        },
+    };
+
+    reader.parser_mut().expect_eof()?;
+    Ok(value)
+}
+
+/// Checks that `input` is what `parse` accepts, failing with the same first
+/// error, without keeping the value. Only the contents of maps and sets,
+/// which must be compared for duplicates, and scalars are built along the
+/// way, so this is cheaper than `parse` for large documents.
+pub fn validate(input: &str) -> Result<(), ParseError> {
+    let options = ReaderOptions::default();
+    let mut reader = Reader::discarding(Parser::new(input), &options);
+
+    if reader.read()?.is_none() {
+        let parser = reader.parser();
+        return Err(parser.error(String::from("unexpected end of input")));
+    }
+
+    reader.parser_mut().expect_eof()
+}
+
+/// Reads the first value in `input`, for EDN embedded in other text. Along
+/// with the value comes the byte offset just past it, where the surrounding
+/// text resumes; nothing after the value is read. Input holding no value is
+/// an error, as for `parse`.
+pub fn parse_value_consuming(input: &str) -> Result<(Value, usize), ParseError> {
+    let options = ReaderOptions::default();
+    let mut reader = Reader::new(Parser::new(input), &options);
+
+    match reader.read()? {
+        Some(value) => Ok((value, reader.parser().token_end)),
+        None => {
+            let parser = reader.parser();
+            Err(parser.error(String::from("unexpected end of input")))
+        },
+    }
+}
+
+/// Like `parse`, but reading on past errors to report as many as it can, for
+/// editor diagnostics.
+///
+/// A bad token or form is left out of the value around it, along with the
+/// rest of its map entry; a wrong closing delimiter still ends its collection,
+/// and the end of the input ends any left open. Exceeding a limit of
+/// `ParserOptions` ends the reading, leaving no value. Errors are given in the
+/// order they were found.
+pub fn parse_recovering(input: &str) -> (Option<Value>, Vec<ParseError>) {
+    let options = ReaderOptions::default();
+    let mut reader = Reader::recovering(Parser::new(input), &options);
+    let result = reader.read();
+    let mut errors = reader.take_errors();
+
+    let value = match result {
+        Ok(Some(value)) => {
+            if let Err(error) = reader.parser_mut().expect_eof() {
+                errors.push(error);
+            }
+
+            Some(value)
+        },
+        Ok(None) => {
+            let parser = reader.parser();
+            errors.push(parser.error(String::from("unexpected end of input")));
+            None
+        },
+        Err(error) => {
+            errors.push(error);
+            None
+        },
+    };
+
+    (value, errors)
+}
+
+/// Parses every top-level value in `input`, in order. Whitespace and comments
+/// between them are skipped; input holding no value at all gives an empty list.
+pub fn parse_all(input: &str) -> Result<Vec<Value>, ParseError> {
+    parse_all_with_options(input, &ReaderOptions::default())
+}
+
+/// Like `parse_all`, but with tagged literals read using the handlers
+/// registered in `options`.
+pub fn parse_all_with_options(input: &str, options: &ReaderOptions) -> Result<Vec<Value>, ParseError> {
+    Values::new(input, options).collect()
+}
+
+// the line and column the lexer gives the character at byte `end` of `text`,
+// or the place just past its last character when `end` is its length
+fn position_at(text: &str, end: usize, columns: ColumnMode) -> (i64, i64) {
+    let mut parser = Parser::with_options(text, ParserOptions { columns, ..ParserOptions::default() });
+    let mut last = None;
+
+    while let Some(c) = parser.next_character() {
+        if parser.offset == end {
+            return (parser.line, parser.character);
+        }
+
+        last = Some((c, parser.line, parser.character));
+    }
+
+    match last {
+        Some(('\n', line, _)) | Some(('\r', line, _)) => (line + 1, 1),
+        Some((c, line, character)) => (line, character + columns.width(c)),
+        None => (1, 1),
+    }
+}
+
+// an error positioned just past the end of `text`
+fn error_after(text: &str, message: String, columns: ColumnMode) -> ParseError {
+    let (line, character) = position_at(text, text.len(), columns);
+    ParseError::new(message, line, character).with_span(Span { start: text.len(), end: text.len(), line, character })
+}
+
+/// Like `parse`, but with the input read from `reader`.
+///
+/// Bytes are decoded as UTF-8 as they are read, so characters split across
+/// reads are reassembled. Invalid UTF-8 and I/O failures are reported as
+/// errors at the position they were found. Only the text of the value is held
+/// in memory; the rest of the input is read just to check that it holds
+/// nothing but whitespace and comments. The value is read again as more of it
+/// arrives, each time its text has doubled, so reading it takes linear time.
+#[cfg(feature = "std")]
+pub fn parse_reader<R: Read>(mut reader: R) -> Result<Value, ParseError> {
+    let mut buffer = [0; 4096];
+    let mut parser = PushParser::new();
+    let mut value = None;
+    // how much input to hold before trying again to read the value
+    let mut wanted = buffer.len();
+
+    loop {
+        let count = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(count) => count,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(parser.error_at_end(format!("I/O error: {}", e))),
+        };
+
+        parser.push(&buffer[..count])?;
+
+        if value.is_none() && (parser.pending_len() >= wanted) {
+            value = parser.read_one()?;
+            wanted = buffer.len().max(2 * parser.pending_len());
+        }
+
+        if value.is_some() {
+            parser.expect_eof(false)?;
+        }
+    }
+
+    let value = match value {
+        Some(value) => value,
+        None => parser.finish_one()?,
+    };
+
+    parser.expect_eof(true)?;
+    Ok(value)
+}
+
+/// Like `parse`, but with the input given as UTF-8 bytes, which may begin
+/// with a byte order mark. Invalid UTF-8 is reported with its offset into
+/// `bytes`, at the position it was found. Error spans are offsets into
+/// `bytes` too.
+pub fn parse_bytes(bytes: &[u8]) -> Result<Value, ParseError> {
+    let bom = if bytes.starts_with(b"\xef\xbb\xbf") { 3 } else { 0 };
+
+    let result = match str::from_utf8(&bytes[bom..]) {
+        Ok(input) => parse(input),
+        Err(e) => {
+            let valid = str::from_utf8(&bytes[bom..bom + e.valid_up_to()]).unwrap();
+            Err(error_after(valid, format!("invalid UTF-8 at byte {}", bom + e.valid_up_to()), ColumnMode::default()))
+        },
+    };
+
+    result.map_err(|error| error.moved(bom, 0, 0))
+}
+
+/// Lexes all of `input`, with whitespace and comments kept as tokens. Fails
+/// at the first lexically invalid token.
+pub fn tokenize(input: &str) -> Result<Vec<Token<'_>>, ParseError> {
+    let mut parser = Parser::new(input);
+    let mut tokens = vec!();
+
+    while let Some(token) = parser.next_token()? {
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::TokenParser;
+
+    fn feed<'s>(parser: &mut dyn TokenParser, s: &'s str) -> Option<Token<'s>> {
+        for c in s.chars() {
+            parser.matches(&c);
+        }
+        parser.get_token(s)
+    }
+
+    #[test]
+    fn initialization_test() {
+        let source = "";
+        let p = Parser::new(source);
+        assert_eq!(source, p.source);
+        assert_eq!(0, p.character);
+        assert_eq!(1, p.line);
+    }
+
+    #[test]
+    fn next_character_test() {
+        let source = "str";
+        let mut p = Parser::new(source);
+
+        let ch_opt = p.next_character();
+        assert_eq!('s', ch_opt.unwrap());
+        assert_eq!(1, p.character);
+
+        let ch_opt = p.next_character();
+        assert_eq!('t', ch_opt.unwrap());
+        assert_eq!(2, p.character);
+
+        let ch_opt = p.next_character();
+        assert_eq!('r', ch_opt.unwrap());
+        assert_eq!(3, p.character);
+
+        let ch_opt = p.next_character();
+        assert_eq!(None, ch_opt);
+        assert_eq!(3, p.character);
+    }
+
+    #[test]
+    fn nil_token_parser_test() {
+        let mut parser = KeywordTokenParser::new("nil", Token::Nil);
+
+        // Matches up to 'nil'
+        assert!(parser.matches(&'n'));
+        assert!(parser.matches(&'i'));
+        assert!(parser.matches(&'l'));
+
+        assert_eq!(Some(Token::Nil), parser.get_token("nil"));
+
+        // Failes to match beyond 'nil'
+        assert!(!parser.matches(&'l'));
+        assert_eq!(None, parser.get_token("nill"));
+
+        // A prefix of 'nil' is not 'nil'
+        let mut parser = KeywordTokenParser::new("nil", Token::Nil);
+        assert!(parser.matches(&'n'));
+        assert!(parser.matches(&'i'));
+        assert_eq!(None, parser.get_token("ni"));
+    }
+
+    #[test]
+    fn debug_test() {
+        let mut parser = SymbolParser::new();
+        feed(&mut parser, "ns/");
+        assert_eq!(
+            "SymbolParser { first: Some('n'), last: Some('/'), length: 3, slash: Some(2), error: None, semicolons: false, last_state: Some(true) }",
+            format!("{:?}", parser));
+
+        // a copy carries on from the same point
+        let mut copy = parser.clone();
+        copy.matches(&'x');
+        assert_eq!(Some(Token::Symbol { namespace: Some("ns"), name: "x" }), copy.get_token("ns/x"));
+        assert_eq!(None, parser.get_token("ns/"));
+
+        let mut p = Parser::new("[a\n b]");
+        p.parse_value().unwrap();
+        assert_eq!("Parser { current_character: Some('a'), line: 1, character: 2, offset: 1, .. }", format!("{:?}", p));
+    }
+
+    #[test]
+    fn symbol_parser_test() {
+        fn parse(s: &str) -> (Option<Token<'_>>, Option<String>) {
+            let mut parser = SymbolParser::new();
+            let token = feed(&mut parser, s);
+            (token, parser.error())
+        }
+
+        assert_eq!((Some(Token::Symbol { namespace: Some("ns"), name: "name" }), None), parse("ns/name"));
+        assert_eq!((None, Some(String::from("trailing `/`"))), parse("foo/"));
+        assert_eq!((None, Some(String::from("more than one `/`"))), parse("a/b/c"));
+        assert_eq!((None, Some(String::from("more than one `/`"))), parse("a//"));
+        assert_eq!((None, None), parse("1a/b"));
+
+        assert_eq!(Ok(Some(Token::Symbol { namespace: Some("ns"), name: "name" })), Parser::new("ns/name").parse_value());
+
+        let mut parser = Parser::new("(foo/)");
+        assert_eq!(Ok(Some(Token::Open(Delimiter::Paren))), parser.parse_value());
+        let error = parser.parse_value().unwrap_err();
+        assert_eq!("invalid symbol `foo/`: trailing `/`", error.message());
+        assert_eq!((1, 2), (error.line(), error.character()));
+
+        let error = Parser::new("a/b/c").parse_value().unwrap_err();
+        assert_eq!("invalid symbol `a/b/c`: more than one `/`", error.message());
+        let error = Parser::new(":a/b/c").parse_value().unwrap_err();
+        assert_eq!("invalid keyword `:a/b/c`: more than one `/`", error.message());
+    }
+
+    #[test]
+    fn namespaced_symbol_test() {
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: "map" })), Parser::new("map").parse_value());
+        assert_eq!(
+            Ok(Some(Token::Symbol { namespace: Some("clojure.core"), name: "map" })),
+            Parser::new("clojure.core/map").parse_value());
+        assert_eq!(
+            Ok(Some(Token::Symbol { namespace: Some("\u{e9}t\u{e9}"), name: "\u{e0}" })),
+            Parser::new("\u{e9}t\u{e9}/\u{e0}").parse_value());
+        assert_eq!(
+            Ok(Some(Token::Symbol { namespace: Some("a.b"), name: "c.d" })),
+            Parser::new("a.b/c.d").parse_value());
+        assert_eq!(Ok(Value::Symbol("clojure.core/map".into())), parse("clojure.core/map"));
+    }
+
+    #[test]
+    fn symbol_character_test() {
+        let is_symbol = |s: &str| matches!(Parser::new(s).parse_value(), Ok(Some(Token::Symbol { .. })));
+
+        let legal = ['*', '+', '!', '-', '_', '?', '$', '%', '&', '=', '<', '>', '.'];
+        let illegal = ['@', '~', '^', '\'', '|', '`'];
+
+        for &c in &legal {
+            assert!(is_symbol(&format!("{}x", c)), "{} first", c);
+            assert!(is_symbol(&format!("x{}y", c)), "{} after the first", c);
+            assert!(is_symbol(&format!("ns/{}x", c)), "{} first in the name", c);
+            assert!(is_symbol(&c.to_string()), "{} alone", c);
+        }
+
+        for &c in &illegal {
+            assert!(!is_symbol(&format!("{}x", c)), "{} first", c);
+            assert!(!is_symbol(&format!("x{}y", c)), "{} after the first", c);
+        }
+
+        for &c in &[':', '#'] {
+            assert!(is_symbol(&format!("x{}y", c)), "{} after the first", c);
+            assert!(!is_symbol(&format!("ns/{}x", c)), "{} first in the name", c);
+        }
+
+        // a leading `+`, `-` or `.` cannot be followed by a digit
+        for &s in &["-1a", ".5", "+.a", "-.5", "ns/-1", "ns/.5", "x/+1y"] {
+            assert_eq!(s.contains("+.") || s.contains("-."), is_symbol(s), "{}", s);
+        }
+
+        assert!(is_symbol("ns/-a") && is_symbol("->") && is_symbol("...") && is_symbol("a-1") && is_symbol("ns/a.1"));
+    }
+
+    #[test]
+    fn unicode_symbol_test() {
+        let symbol = |namespace, name| Ok(Some(Token::Symbol { namespace, name }));
+
+        assert_eq!(symbol(None, "caf\u{e9}"), Parser::new("caf\u{e9}").parse_value());
+        assert_eq!(symbol(None, "cafe\u{301}"), Parser::new("cafe\u{301}").parse_value());
+        assert_eq!(
+            symbol(Some("\u{6570}\u{5b66}"), "\u{5e73}\u{65b9}\u{6839}"),
+            Parser::new("\u{6570}\u{5b66}/\u{5e73}\u{65b9}\u{6839}").parse_value());
+        assert_eq!(symbol(None, "x\u{663}"), Parser::new("x\u{663}").parse_value());
+
+        // neither digits nor combining marks can start a symbol or its name
+        assert!(Parser::new("1abc").parse_value().is_err());
+        assert!(Parser::new("\u{663}x").parse_value().is_err());
+        assert!(Parser::new("\u{301}e").parse_value().is_err());
+        assert!(Parser::new("ns/\u{301}e").parse_value().is_err());
+        assert!(Parser::new("ns/1").parse_value().is_err());
+    }
+
+    #[test]
+    fn symbol_colon_hash_test() {
+        let symbol = |namespace, name| Ok(Some(Token::Symbol { namespace, name }));
+
+        assert_eq!(symbol(None, "foo#"), Parser::new("foo#").parse_value());
+        assert_eq!(symbol(None, "a:b"), Parser::new("a:b").parse_value());
+        assert_eq!(symbol(Some("ns"), "a:b"), Parser::new("ns/a:b").parse_value());
+        assert_eq!(symbol(Some("a#"), "b#c"), Parser::new("a#/b#c").parse_value());
+        assert_eq!(symbol(None, "+:x"), Parser::new("+:x").parse_value());
+        assert_eq!(symbol(None, "a:"), Parser::new("a:").parse_value());
+
+        assert_eq!("invalid symbol `a::b`: `::` after the start", Parser::new("a::b").parse_value().unwrap_err().message());
+        assert_eq!("invalid token `ns/:a`", Parser::new("ns/:a").parse_value().unwrap_err().message());
+
+        // a leading `:` always makes a keyword
+        assert_eq!(
+            Ok(Some(Token::Keyword { namespace: None, name: String::from("a:b"), auto_resolved: false })),
+            Parser::new(":a:b").parse_value());
+        assert_eq!("invalid keyword `:a::`: `::` after the start", Parser::new(":a::").parse_value().unwrap_err().message());
+    }
+
+    #[test]
+    fn slash_symbol_test() {
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: "/" })), Parser::new("/").parse_value());
+        assert_eq!(Some(Ok(Token::Symbol { namespace: None, name: "/" })), Parser::new("(/ 1 2)").nth(1));
+
+        let error = Parser::new("/x").parse_value().unwrap_err();
+        assert_eq!("invalid symbol `/x`: leading `/`", error.message());
+        let error = Parser::new("//").parse_value().unwrap_err();
+        assert_eq!("invalid symbol `//`: leading `/`", error.message());
+        let error = Parser::new("x//y").parse_value().unwrap_err();
+        assert_eq!("invalid symbol `x//y`: more than one `/`", error.message());
+        assert_eq!("invalid keyword `:/`: `/` is not a keyword name", Parser::new(":/").parse_value().unwrap_err().message());
+    }
+
+    #[test]
+    fn slash_error_test() {
+        let error = |s| {
+            let error = ::parse(s).unwrap_err();
+            (error.message().to_string(), error.line(), error.character())
+        };
+
+        assert_eq!((String::from("invalid symbol `a/b/c`: more than one `/`"), 1, 2), error("[a/b/c]"));
+        assert_eq!((String::from("invalid symbol `foo/`: trailing `/`"), 2, 5), error("{:a 1\n :b foo/}"));
+        assert_eq!((String::from("invalid keyword `:ns/`: trailing `/`"), 1, 4), error("#{ :ns/ }"));
+    }
+
+    #[test]
+    fn auto_resolved_keyword_test() {
+        assert_eq!(
+            Ok(Some(Token::Keyword { namespace: None, name: String::from("foo"), auto_resolved: true })),
+            Parser::new("::foo").parse_value());
+        assert_eq!(
+            Ok(Some(Token::Keyword { namespace: Some("my.ns".into()), name: "bar".into(), auto_resolved: true })),
+            Parser::new("::my.ns/bar").parse_value());
+        assert!(Parser::new(":::foo").parse_value().is_err());
+        assert!(Parser::new("::").parse_value().is_err());
+        assert!(Parser::new(":foo:").parse_value().is_ok());
+
+        let value = parse("::my.ns/bar").unwrap();
+        assert_eq!("::my.ns/bar", value.to_string());
+        assert!(value != parse(":my.ns/bar").unwrap());
+    }
+
+    #[test]
+    fn keyword_parser_test() {
+        fn parse(s: &str) -> Option<Token<'_>> {
+            feed(&mut KeywordParser::new(), s)
+        }
+
+        assert_eq!(Some(Token::Keyword { namespace: None, name: String::from("foo"), auto_resolved: false }), parse(":foo"));
+        assert_eq!(Some(Token::Keyword { namespace: Some("my.ns".into()), name: "bar".into(), auto_resolved: false }), parse(":my.ns/bar"));
+        assert_eq!(Some(Token::Keyword { namespace: None, name: String::from("a?b!"), auto_resolved: false }), parse(":a?b!"));
+
+        assert_eq!(None, parse(":"));
+        assert_eq!(None, parse("::"));
+        assert_eq!(None, parse(":::foo"));
+        assert_eq!(None, parse(":a/b/c"));
+        assert_eq!(None, parse(":ns/"));
+        assert_eq!(None, parse("foo"));
+
+        let s = ":foo :ns/bar :";
+        let mut p = Parser::new(s);
+        assert_eq!(Ok(Some(Token::Keyword { namespace: None, name: String::from("foo"), auto_resolved: false })), p.parse_value());
+        assert_eq!(Ok(Some(Token::Keyword { namespace: Some("ns".into()), name: "bar".into(), auto_resolved: false })), p.parse_value());
+        assert!(p.parse_value().is_err());
+    }
+
+    #[test]
+    fn integer_token_parser_test() {
+        fn parse(s: &str) -> Option<Token<'_>> {
+            feed(&mut IntegerTokenParser::new(), s)
+        }
+
+        assert_eq!(Some(Token::Integer(0)), parse("0"));
+        assert_eq!(Some(Token::Integer(0)), parse("-0"));
+        assert_eq!(Some(Token::Integer(42)), parse("42"));
+        assert_eq!(Some(Token::Integer(-17)), parse("-17"));
+        assert_eq!(Some(Token::Integer(3)), parse("+3"));
+        assert_eq!(Some(Token::Integer(7)), parse("007"));
+        assert_eq!(Some(Token::Integer(i64::MAX)), parse("9223372036854775807"));
+        assert_eq!(Some(Token::Integer(i64::MIN)), parse("-9223372036854775808"));
+
+        assert_eq!(None, parse("+"));
+        assert_eq!(None, parse("-"));
+        assert_eq!(None, parse("12a"));
+        assert_eq!(None, parse("1.2"));
+        assert_eq!(None, parse("1-2"));
+    }
+
+    #[test]
+    fn radix_integer_test() {
+        fn parse(s: &str) -> Option<Token<'_>> {
+            feed(&mut IntegerTokenParser::new(), s)
+        }
+
+        assert_eq!(Some(Token::Integer(31)), parse("0x1F"));
+        assert_eq!(Some(Token::Integer(-255)), parse("-0Xff"));
+        assert_eq!(Some(Token::Integer(255)), parse("0377"));
+        assert_eq!(Some(Token::Integer(-8)), parse("-010"));
+        assert_eq!(Some(Token::Integer(10)), parse("2r1010"));
+        assert_eq!(Some(Token::Integer(255)), parse("16rFF"));
+        assert_eq!(Some(Token::Integer(35)), parse("36rZ"));
+        assert_eq!(Some(Token::Integer(-35)), parse("-36Rz"));
+        assert_eq!(Some(Token::Integer(i64::MIN)), parse("-0x8000000000000000"));
+
+        assert_eq!(None, parse("2r1012"));
+        assert_eq!(None, parse("09"));
+        assert_eq!(None, parse("0x"));
+        assert_eq!(None, parse("0xG"));
+        assert_eq!(None, parse("1x1"));
+        assert_eq!(None, parse("37r1"));
+        assert_eq!(None, parse("1r0"));
+        assert_eq!(None, parse("123r4"));
+
+        assert_eq!("invalid token `2r1012`", Parser::new("2r1012").parse_value().unwrap_err().message());
+        assert_eq!(Ok(Value::Vector(vec!(Value::Integer(26), Value::Integer(8)))), ::parse("[0x1a 010]"));
+    }
+
+    #[test]
+    fn strict_numbers_test() {
+        let options = ParserOptions { strict_numbers: true, ..ParserOptions::default() };
+        let strict = |s| Parser::with_options(s, options.clone()).parse_value();
+        let lenient = |s| Parser::new(s).parse_value();
+
+        for s in &["0", "-0", "+0", "10", "0N", "0x1F"] {
+            assert_eq!(lenient(s), strict(s));
+        }
+
+        assert_eq!(Ok(Some(Token::Integer(7))), lenient("007"));
+        assert_eq!(Ok(Some(Token::Integer(0))), lenient("-00"));
+        assert_eq!(Ok(Some(Token::Integer(1))), lenient("01"));
+
+        for s in &["00", "01", "007", "-007", "-00", "+01", "007N"] {
+            assert_eq!(format!("invalid token `{}`", s), strict(s).unwrap_err().message());
+        }
+    }
+
+    #[test]
+    fn allow_comments_test() {
+        let options = ParserOptions { allow_comments: false, ..ParserOptions::default() };
+        let without_comments = |s| Parser::with_options(s, options.clone()).parse_value();
+
+        assert_eq!(Ok(None), Parser::new(";foo").parse_value());
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: ";foo" })), without_comments(";foo"));
+
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: "a" })), Parser::new("a;b").parse_value());
+        assert_eq!(Ok(Some(Token::Symbol { namespace: Some("a;"), name: ";b" })), without_comments("a;/;b"));
+        assert_eq!(
+            Ok(Some(Token::Keyword { namespace: None, name: String::from("x;"), auto_resolved: false })),
+            without_comments(":x;"));
+
+        let options = {
+            let mut reader_options = ReaderOptions::default();
+            reader_options.parser = options.clone();
+            reader_options
+        };
+        assert_eq!(
+            Ok(Value::vector(vec!(Value::Symbol(";foo".into()), Value::Integer(1)))),
+            parse_with_options("[;foo 1]", &options));
+        assert_eq!(Ok(Value::vector(vec!(Value::Integer(1)))), parse("[1 ;foo\n]"));
+
+        // a `;` after a number is part of it, and so makes it invalid
+        assert_eq!("invalid token `1;`", without_comments("1;").unwrap_err().message());
+    }
+
+    #[test]
+    fn integer_overflow_test() {
+        let error = Parser::new("9223372036854775808").parse_value().unwrap_err();
+        assert_eq!("integer literal `9223372036854775808` out of range", error.message());
+
+        assert_eq!(Ok(Some(Token::Integer(i64::MIN))), Parser::new("-9223372036854775808").parse_value());
+        let error = Parser::new("-9223372036854775809").parse_value().unwrap_err();
+        assert_eq!("integer literal `-9223372036854775809` out of range", error.message());
+
+        let error = Parser::new("0x10000000000000000").parse_value().unwrap_err();
+        assert_eq!("integer literal `0x10000000000000000` out of range", error.message());
+        assert_eq!(
+            Ok(Some(Token::BigInteger(String::from("9223372036854775808")))),
+            Parser::new("9223372036854775808N").parse_value());
+        assert_eq!("invalid token `2r1012`", Parser::new("2r1012").parse_value().unwrap_err().message());
+    }
+
+    #[test]
+    fn ratio_test() {
+        assert_eq!(Ok(Some(Token::Ratio { numerator: 1, denominator: 2 })), Parser::new("1/2").parse_value());
+        assert_eq!(Ok(Some(Token::Ratio { numerator: -3, denominator: 4 })), Parser::new("-3/4").parse_value());
+        assert_eq!(Ok(Some(Token::Ratio { numerator: 2, denominator: 3 })), Parser::new("+4/6").parse_value());
+        assert_eq!(Ok(Some(Token::Integer(2))), Parser::new("6/3").parse_value());
+
+        assert_eq!("ratio `1/0` has a zero denominator", Parser::new("1/0").parse_value().unwrap_err().message());
+        assert_eq!("ratio `1/99999999999999999999` out of range",
+            Parser::new("1/99999999999999999999").parse_value().unwrap_err().message());
+        assert_eq!("invalid token `1/-2`", Parser::new("1/-2").parse_value().unwrap_err().message());
+        assert!(Parser::new("1/").parse_value().is_err());
+
+        let value = parse("[22/7 1/2]").unwrap();
+        assert_eq!(Value::vector(vec!(
+            Value::Ratio { numerator: 22, denominator: 7 },
+            Value::Ratio { numerator: 1, denominator: 2 })), value);
+        assert_eq!("[22/7 1/2]", value.to_string());
+    }
+
+    // numeric literals read the same as when the parsers buffered characters
+    #[test]
+    fn numeric_literal_test() {
+        let cases = [
+            ("-0", "Ok(Some(Integer(0)))"),
+            ("007", "Ok(Some(Integer(7)))"),
+            ("-0Xff", "Ok(Some(Integer(-255)))"),
+            ("36rZ", "Ok(Some(Integer(35)))"),
+            ("+5N", "Ok(Some(BigInteger(\"5\")))"),
+            ("-7N", "Ok(Some(BigInteger(\"-7\")))"),
+            ("+2.0E-4", "Ok(Some(Float(0.0002)))"),
+            ("3.e2", "Ok(Some(Float(300.0)))"),
+            ("-0.0", "Ok(Some(Float(-0.0)))"),
+            ("+2M", "Ok(Some(BigDecimal(\"2\")))"),
+            ("-0.25M", "Ok(Some(BigDecimal(\"-0.25\")))"),
+            ("09", "Err(\"invalid token `09`\")"),
+            ("0x", "Err(\"invalid token `0x`\")"),
+            ("1e+", "Err(\"invalid token `1e+`\")"),
+            ("1.5MM", "Err(\"invalid token `1.5MM`\")"),
+            ("+-", "Ok(Some(Symbol { namespace: None, name: \"+-\" }))"),
+        ];
+
+        for &(input, expected) in cases.iter() {
+            let result = Parser::new(input).parse_value().map_err(|e| e.message().to_string());
+            assert_eq!(expected, format!("{:?}", result), "{}", input);
+        }
+    }
+
+    #[test]
+    fn big_integer_token_parser_test() {
+        fn parse(s: &str) -> Option<Token<'_>> {
+            feed(&mut IntegerTokenParser::new(), s)
+        }
+
+        let big = "123456789012345678901234567890";
+        assert_eq!(Some(Token::BigInteger(String::from(big))), parse(&format!("{}N", big)));
+        assert_eq!(Some(Token::BigInteger(format!("-{}", big))), parse(&format!("-{}N", big)));
+        assert_eq!(Some(Token::BigInteger(String::from("9223372036854775808"))), parse("+9223372036854775808N"));
+        assert_eq!(Some(Token::BigInteger(String::from("1"))), parse("1N"));
+
+        // prefixes are read the same as without the suffix
+        assert_eq!(Some(Token::BigInteger(String::from("511"))), parse("0777N"));
+        assert_eq!(Some(Token::BigInteger(String::from("255"))), parse("0xFFN"));
+        assert_eq!(Some(Token::BigInteger(String::from("-5"))), parse("-2r101N"));
+        assert_eq!(Some(Token::BigInteger(String::from("0"))), parse("0N"));
+        assert_eq!(Some(Token::BigInteger(String::from("18446744073709551615"))), parse("0xFFFFFFFFFFFFFFFFN"));
+        assert_eq!(Some(Token::Integer(23)), parse("36rN"));
+        assert_eq!(None, parse("0xN"));
+        assert_eq!(None, feed(&mut IntegerTokenParser::strict(), "0777N"));
+
+        assert_eq!(None, parse("N"));
+        assert_eq!(None, parse("-N"));
+        assert_eq!(None, parse("123Nx"));
+        assert_eq!(None, parse("123NN"));
+        assert_eq!(None, parse("12N3"));
+    }
+
+    #[test]
+    fn float_token_parser_test() {
+        fn parse(s: &str) -> Option<Token<'_>> {
+            feed(&mut FloatTokenParser::new(), s)
+        }
+
+        assert_eq!(Some(Token::Float(3.25)), parse("3.25"));
+        assert_eq!(Some(Token::Float(-0.5)), parse("-0.5"));
+        assert_eq!(Some(Token::Float(6.022e23)), parse("6.022e23"));
+        assert_eq!(Some(Token::Float(1.0E-10)), parse("1.0E-10"));
+        assert_eq!(Some(Token::Float(1.0)), parse("1."));
+        assert_eq!(Some(Token::Float(1e10)), parse("1e10"));
+        assert_eq!(Some(Token::Float(2e5)), parse("+2E+5"));
+        assert_eq!(Some(Token::Float(1e5)), parse("1.e5"));
+
+        assert_eq!(None, parse(".5"));
+        assert_eq!(None, parse("42"));
+        assert_eq!(None, parse("-"));
+        assert_eq!(None, parse("1e"));
+        assert_eq!(None, parse("1e+"));
+        assert_eq!(None, parse("1.2.3"));
+        assert_eq!(None, parse("1e5.0"));
+        assert_eq!(None, parse("-.5"));
+    }
+
+    #[test]
+    fn big_decimal_token_parser_test() {
+        fn parse(s: &str) -> Option<Token<'_>> {
+            feed(&mut FloatTokenParser::new(), s)
+        }
+
+        assert_eq!(Some(Token::BigDecimal(String::from("1"))), parse("1M"));
+        assert_eq!(Some(Token::BigDecimal(String::from("1.0"))), parse("1.0M"));
+        assert_eq!(Some(Token::BigDecimal(String::from("1e3"))), parse("1e3M"));
+        assert_eq!(Some(Token::BigDecimal(String::from("-3.14"))), parse("-3.14M"));
+        assert_eq!(Some(Token::BigDecimal(String::from("3.14"))), parse("+3.14M"));
+        assert_eq!(Some(Token::BigDecimal(String::from("0.10000000000000000000001"))), parse("0.10000000000000000000001M"));
+
+        assert_eq!(None, parse("M"));
+        assert_eq!(None, parse("-M"));
+        assert_eq!(None, parse("1eM"));
+        assert_eq!(None, parse("1MM"));
+        assert_eq!(None, parse("1M2"));
+    }
+
+    #[test]
+    fn string_parser_test() {
+        fn parse(s: &str) -> Option<Token<'_>> {
+            feed(&mut StringParser::new(), s)
+        }
+
+        assert_eq!(Some(Token::String(String::new())), parse("\"\""));
+        assert_eq!(Some(Token::String(String::from("a \"quoted\" word"))), parse("\"a \\\"quoted\\\" word\""));
+        assert_eq!(Some(Token::String(String::from("\" \\ \n \t \r \u{c}"))), parse("\"\\\" \\\\ \\n \\t \\r \\f\""));
+        assert_eq!(Some(Token::String(String::from("two\nlines"))), parse("\"two\nlines\""));
+
+        assert_eq!(None, parse("\"unterminated"));
+        assert_eq!(None, parse("\"bad \\q escape\""));
+        assert_eq!(None, parse("no quote\""));
+    }
+
+    #[test]
+    fn unicode_escape_test() {
+        fn parse(s: &str) -> (Option<Token<'_>>, Option<String>) {
+            let mut parser = StringParser::new();
+            let token = feed(&mut parser, s);
+            (token, parser.error())
+        }
+
+        assert_eq!((Some(Token::String(String::from("\u{e9}"))), None), parse("\"\\u00e9\""));
+        assert_eq!((Some(Token::String(String::from("caf\u{e9} \u{4e2d}"))), None), parse("\"caf\\u00E9 \\u4e2d\""));
+        assert_eq!((Some(Token::String(String::from("\u{ffff}"))), None), parse("\"\\uffff\""));
+
+        let (token, error) = parse("\"\\u12\"");
+        assert_eq!(None, token);
+        assert_eq!(Some(String::from("invalid unicode escape \\u12: expected four hex digits")), error);
+
+        let (token, error) = parse("\"\\u12g4\"");
+        assert_eq!(None, token);
+        assert_eq!(Some(String::from("invalid unicode escape \\u12: expected four hex digits")), error);
+
+        let (token, error) = parse("\"\\ud800\"");
+        assert_eq!(None, token);
+        assert_eq!(Some(String::from("invalid unicode escape \\ud800: not a unicode scalar value")), error);
+
+        let (token, error) = parse("\"\\q\"");
+        assert_eq!(None, token);
+        assert_eq!(Some(String::from("invalid escape sequence \\q")), error);
+
+        let (token, error) = parse("\"open");
+        assert_eq!(None, token);
+        assert_eq!(Some(String::from("unterminated string")), error);
+    }
+
+    #[test]
+    fn parse_string_test() {
+        let s = "\"spans\ntwo lines\" after";
+        let mut p = Parser::new(s);
+        assert_eq!(Ok(Some(Token::String(String::from("spans\ntwo lines")))), p.parse_value());
+        assert_eq!(2, p.line);
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: "after" })), p.parse_value());
+        assert_eq!(2, p.line);
+
+        let s = "\"\" \"with space\"";
+        let mut p = Parser::new(s);
+        assert_eq!(Ok(Some(Token::String(String::new()))), p.parse_value());
+        assert_eq!(Ok(Some(Token::String(String::from("with space")))), p.parse_value());
+
+        // an unterminated string is not mistaken for a symbol
+        let s = "\"never\nclosed";
+        let mut p = Parser::new(s);
+        assert!(p.parse_value().is_err());
+        assert_eq!(2, p.line);
+    }
+
+    #[test]
+    fn character_parser_test() {
+        fn parse(s: &str) -> Option<Token<'_>> {
+            feed(&mut CharacterParser::new(), s)
+        }
+
+        assert_eq!(Some(Token::Character('a')), parse("\\a"));
+        assert_eq!(Some(Token::Character('f')), parse("\\f"));
+        assert_eq!(Some(Token::Character('u')), parse("\\u"));
+        assert_eq!(Some(Token::Character('\\')), parse("\\\\"));
+        assert_eq!(Some(Token::Character('\n')), parse("\\newline"));
+        assert_eq!(Some(Token::Character(' ')), parse("\\space"));
+        assert_eq!(Some(Token::Character('\t')), parse("\\tab"));
+        assert_eq!(Some(Token::Character('\r')), parse("\\return"));
+        assert_eq!(Some(Token::Character('\u{c}')), parse("\\formfeed"));
+        assert_eq!(Some(Token::Character('\u{8}')), parse("\\backspace"));
+        assert_eq!(Some(Token::Character('\u{e9}')), parse("\\u00e9"));
+        assert_eq!(Some(Token::Character('\u{4e2d}')), parse("\\u4E2D"));
+
+        assert_eq!(None, parse("\\foo"));
+        assert_eq!(None, parse("\\spa"));
+        assert_eq!(None, parse("\\u00e"));
+        assert_eq!(None, parse("\\ud800"));
+        assert_eq!(None, parse("\\"));
+        assert_eq!(None, parse("a"));
+    }
+
+    #[test]
+    fn parse_character_test() {
+        let s = "\\newlines \\a \\space\\tab";
+        let mut p = Parser::new(s);
+        assert_eq!(Ok(Some(Token::Character('\n'))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: "s" })), p.parse_value());
+        assert_eq!(Ok(Some(Token::Character('a'))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Character(' '))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Character('\t'))), p.parse_value());
+        assert_eq!(Ok(None), p.parse_value());
+
+        let mut character_parser = CharacterParser::new();
+        assert_eq!(None, feed(&mut character_parser, "\\foo"));
+        assert_eq!(Some(String::from("unsupported character \\foo")), character_parser.error());
+
+        assert!(Parser::new("\\foo").parse_value().is_err());
+        assert!(Parser::new("\\ab").parse_value().is_err());
+        assert_eq!(Ok(Some(Token::Character('"'))), Parser::new("\\\"").parse_value());
+    }
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(Ok(Value::Integer(42)), parse("42"));
+        assert_eq!(Ok(Value::Symbol("foo".into())), parse("foo"));
+        assert_eq!(Ok(Value::Keyword { namespace: Some("a".into()), name: "b".into(), auto_resolved: false }), parse("  :a/b\n"));
+        assert_eq!(Ok(Value::String(String::from("two words"))), parse("\"two words\""));
+
+        let error = parse("1 2").unwrap_err();
+        assert_eq!("unexpected trailing content", error.message());
+        assert_eq!((1, 3), (error.line(), error.character()));
+
+        let error = parse("nil\n  \"x").unwrap_err();
+        assert_eq!((2, 3), (error.line(), error.character()));
+
+        let error = parse("").unwrap_err();
+        assert_eq!("unexpected end of input", error.message());
+
+        let error = parse(" \n ").unwrap_err();
+        assert_eq!("unexpected end of input", error.message());
+
+        assert_eq!(
+            Ok(Value::Vector(vec!(Value::Integer(1), Value::Integer(2)))),
+            parse("[1 ; a comment\n 2]"));
+        assert_eq!(
+            Ok(Value::Map(vec!(
+                (Value::Keyword { namespace: None, name: "a".into(), auto_resolved: false }, Value::Integer(1)),
+                (Value::Keyword { namespace: None, name: "b".into(), auto_resolved: false }, Value::Integer(2))))),
+            parse("{:a 1 ; first\n :b 2 ; second\n}"));
+        assert_eq!(Ok(Value::Nil), parse("nil ; no trailing newline"));
+
+        let error = parse("; nothing but a comment").unwrap_err();
+        assert_eq!("unexpected end of input", error.message());
+
+        let error = parse("[1 ; 2]").unwrap_err();
+        assert_eq!("unexpected end of input, expected `]`", error.message());
+
+        let error = parse("  12a").unwrap_err();
+        assert_eq!("invalid token `12a`", error.message());
+        assert_eq!((1, 3), (error.line(), error.character()));
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let s = "[1\n  \"never closed";
+        let mut p = Parser::new(s);
+        assert_eq!(Ok(Some(Token::Open(Delimiter::Bracket))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Integer(1))), p.parse_value());
+
+        let error = p.parse_value().unwrap_err();
+        assert_eq!("unterminated string", error.message());
+        assert_eq!((2, 3), (error.line(), error.character()));
+
+        let error = parse("nil\n ]").unwrap_err();
+        assert_eq!("unexpected trailing content", error.message());
+
+        let error = parse(" ]").unwrap_err();
+        assert_eq!("unexpected closing delimiter `]`", error.message());
+        assert_eq!((1, 2), (error.line(), error.character()));
+
+        #[cfg(feature = "std")] {
+            let error: Box<dyn std::error::Error> = Box::new(error);
+            assert_eq!("unexpected closing delimiter `]` at line 1, character 2", error.to_string());
+        }
+    }
+
+    #[test]
+    fn error_span_test() {
+        let s = "[1 \"never\n closed";
+        let span = parse(s).unwrap_err().span().unwrap();
+        assert_eq!("\"never\n closed", &s[span.start..span.end]);
+        assert_eq!((1, 4), (span.line, span.character));
+
+        let s = "{[1 2] 1\n [1 2] 2}";
+        let span = parse(s).unwrap_err().span().unwrap();
+        assert_eq!("[1 2]", &s[span.start..span.end]);
+        assert_eq!((2, 2), (span.line, span.character));
+
+        // errors of structure point between tokens
+        assert_eq!(Some(Span { start: 3, end: 3, line: 1, character: 3 }), parse("[1 ").unwrap_err().span());
+        assert_eq!(Some(Span { start: 5, end: 5, line: 1, character: 3 }), parse_bytes(b"\xef\xbb\xbf(1]").unwrap_err().span());
+
+        let mut parser = PushParser::new();
+        assert!(parser.feed(b"1\n 2 12a").is_ok());
+        assert_eq!(Some(Span { start: 5, end: 8, line: 2, character: 4 }), parser.finish().unwrap_err().span());
+    }
+
+    #[test]
+    fn iterator_test() {
+        assert_eq!(
+            vec!(Token::Nil, Token::Whitespace(" "), Token::Boolean(true)),
+            Parser::new("nil true").collect::<Result<Vec<_>, _>>().unwrap());
+
+        assert_eq!(
+            vec!(
+                Token::Open(Delimiter::Bracket),
+                Token::Integer(1),
+                Token::Whitespace(", "),
+                Token::Comment(String::from("; one")),
+                Token::Whitespace("\n"),
+                Token::Close(Delimiter::Bracket)),
+            Parser::new("[1, ; one\n]").collect::<Result<Vec<_>, _>>().unwrap());
+
+        assert_eq!(0, Parser::new("").count());
+
+        // the end of input is `None`, while an error is an item of its own
+        // that ends iteration, even with more input after it
+        let mut p = Parser::new("a \"open");
+        assert_eq!(Some(Ok(Token::Symbol { namespace: None, name: "a" })), p.next());
+        assert_eq!(Some(Ok(Token::Whitespace(" "))), p.next());
+        assert_eq!("unterminated string", p.next().unwrap().unwrap_err().message());
+        assert_eq!(None, p.next());
+
+        let tokens: Vec<_> = Parser::new("12a b").collect();
+        assert_eq!(1, tokens.len());
+        assert_eq!("invalid token `12a`", tokens[0].as_ref().unwrap_err().message());
+    }
+
+    #[test]
+    fn tokenize_test() {
+        assert_eq!(
+            Ok(vec!(
+                Token::Comment(String::from(";; config")),
+                Token::Whitespace("\n"),
+                Token::Open(Delimiter::Brace),
+                Token::Keyword { namespace: None, name: String::from("port"), auto_resolved: false },
+                Token::Whitespace(" "),
+                Token::Integer(8080),
+                Token::Whitespace(", "),
+                Token::Keyword { namespace: None, name: String::from("tags"), auto_resolved: false },
+                Token::Whitespace(" "),
+                Token::OpenSet,
+                Token::String(String::from("a")),
+                Token::Close(Delimiter::Brace),
+                Token::Close(Delimiter::Brace))),
+            tokenize(";; config\n{:port 8080, :tags #{\"a\"}}"));
+        assert_eq!(Ok(vec!()), tokenize(""));
+
+        let error = tokenize("[\"open]").unwrap_err();
+        assert_eq!("unterminated string", error.message());
+        assert_eq!((1, 2), (error.line(), error.character()));
+    }
+
+    // hands out at most `chunk` bytes per read
+    #[cfg(feature = "std")]
+    struct ChunkedReader<'a> {
+        bytes: &'a [u8],
+        chunk: usize,
+    }
+
+    #[cfg(feature = "std")]
+    impl<'a> Read for ChunkedReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let count = self.chunk.min(buf.len()).min(self.bytes.len());
+            buf[..count].copy_from_slice(&self.bytes[..count]);
+            self.bytes = &self.bytes[count..];
+            Ok(count)
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn parse_reader_test() {
+        assert_eq!(
+            Ok(Value::Vector(vec!(Value::Integer(1), Value::String(String::from("two"))))),
+            parse_reader(io::Cursor::new("[1 \"two\"]")));
+
+        let s = "{:name \"Zo\u{eb}\" :greeting \"\u{4f60}\u{597d} \u{1f44b}\"}";
+        for chunk in 1..4 {
+            assert_eq!(parse(s), parse_reader(ChunkedReader { bytes: s.as_bytes(), chunk }));
+        }
+
+        // the input after the value is checked as it arrives
+        for s in &["", " ;c", "1 2", "42 ; done", "[1]\n; c\n  x", "(1 2", "\"a"] {
+            for chunk in 1..4 {
+                assert_eq!(parse(s), parse_reader(ChunkedReader { bytes: s.as_bytes(), chunk }));
+            }
+        }
+
+        let s = format!("[{}]\n\n x", vec!("12345"; 5000).join(" "));
+        assert_eq!(parse(&s), parse_reader(ChunkedReader { bytes: s.as_bytes(), chunk: 1000 }));
+
+        let error = parse_reader(io::Cursor::new(&b"[1\n \"a\xffb\"]"[..])).unwrap_err();
+        assert_eq!("invalid UTF-8 at byte 6", error.message());
+        assert_eq!((2, 4), (error.line(), error.character()));
+
+        let error = parse_reader(io::Cursor::new(&b"\"\xe2\x82"[..])).unwrap_err();
+        assert_eq!("invalid UTF-8 at byte 1", error.message());
+    }
+
+    // also run with `--no-default-features`, where only `alloc` is used
+    #[test]
+    fn alloc_only_test() {
+        assert_eq!(Ok(Value::Integer(42)), parse("42"));
+        assert_eq!(Ok(Value::String(String::from("ok"))), parse_bytes(b"\"ok\""));
+        assert_eq!("keyword", parse(":ready").unwrap().type_name());
+    }
+
+    #[test]
+    fn parse_recovering_test() {
+        let (value, errors) = parse_recovering("[1 2a {:a 1 :b} \n (3 ]\n 4]");
+        assert_eq!(Some(parse("[1 {:a 1} (3) 4]").unwrap()), value);
+        assert_eq!(
+            vec!(
+                ("invalid token `2a`", 1, 4),
+                ("map literal contains an odd number of forms", 1, 15),
+                ("unexpected closing delimiter `]`, expected `)`", 2, 5)),
+            errors.iter().map(|e| (e.message(), e.line(), e.character())).collect::<Vec<_>>());
+
+        let (value, errors) = parse_recovering("{:a 1 :a 2 :b \"open");
+        assert_eq!(Some(parse("{:a 1}").unwrap()), value);
+        assert_eq!(
+            vec!("duplicate map key :a", "unterminated string", "unexpected end of input, expected `}`"),
+            errors.iter().map(|e| e.message()).collect::<Vec<_>>());
+
+        assert_eq!((Some(Value::Integer(1)), vec!()), parse_recovering(" 1 "));
+        assert_eq!(vec!("unexpected trailing content"), parse_recovering("1 2").1.iter().map(|e| e.message()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn validate_test() {
+        assert_eq!(Ok(()), validate("[1 (2 [3]) {:a [4 5] :b #{[6]}} #inst \"1985-04-12T23:20:50Z\"]"));
+        assert_eq!(Ok(()), validate(" ; config\n{:port 8080}"));
+
+        for s in &["[1 2", "[{:a 1 :a 2}]", "[#{[1 2] [1 2]}]", "([1] ]", "[12a]", "1 2", "", "[#inst [1]]"] {
+            assert_eq!(parse(s).map(|_| ()), validate(s));
+        }
+
+        let mut nested = "[".repeat(300);
+        nested.push_str(&"]".repeat(300));
+        assert_eq!("collections nested deeper than 256 levels", validate(&nested).unwrap_err().message());
+    }
+
+    #[test]
+    fn parse_bytes_test() {
+        let expected = Ok(Value::Map(vec!((Value::Symbol("a".into()), Value::String(String::from("\u{e9}"))))));
+
+        assert_eq!(expected, parse_bytes("{a \"\u{e9}\"}".as_bytes()));
+        assert_eq!(expected, parse_bytes("\u{feff}{a \"\u{e9}\"}".as_bytes()));
+
+        // only a leading mark is stripped
+        assert!(parse_bytes("\u{feff}\u{feff}1".as_bytes()).is_err());
+
+        let error = parse_bytes(b"\xef\xbb\xbf[1\n \xc3]").unwrap_err();
+        assert_eq!("invalid UTF-8 at byte 7", error.message());
+        assert_eq!((2, 2), (error.line(), error.character()));
+
+        let error = parse_bytes(b"\"\xff\"").unwrap_err();
+        assert_eq!("invalid UTF-8 at byte 1", error.message());
+
+        // a lone `\r` ends a line, as for the lexer
+        let error = parse_bytes(b"[1\r\r\n \xc3]").unwrap_err();
+        assert_eq!((3, 2), (error.line(), error.character()));
+    }
+
+    #[test]
+    fn span_test() {
+        let s = "[1\n  foo \"b\u{e9}r\"]";
+        let mut p = Parser::new(s);
+        let mut spans = vec!();
+
+        while let Some((token, span)) = p.next_spanned().unwrap() {
+            spans.push((token, span));
+        }
+
+        assert_eq!(7, spans.len());
+        assert_eq!((Token::Symbol { namespace: None, name: "foo" }, Span { start: 5, end: 8, line: 2, character: 3 }), spans[3]);
+        assert_eq!((Token::String(String::from("b\u{e9}r")), Span { start: 9, end: 15, line: 2, character: 7 }), spans[5]);
+        assert_eq!("\"b\u{e9}r\"", &s[spans[5].1.start..spans[5].1.end]);
+        assert_eq!(Span { start: 15, end: 16, line: 2, character: 12 }, spans[6].1);
+        assert_eq!(Ok(None), p.next_spanned());
+    }
+
+    #[test]
+    fn column_mode_test() {
+        let s = "[\"caf\u{e9} \u{1f600}\" x]";
+
+        for &(columns, expected) in &[(ColumnMode::Characters, 11), (ColumnMode::Bytes, 15), (ColumnMode::Utf16, 12)] {
+            let mut p = Parser::with_options(s, ParserOptions { columns, ..ParserOptions::default() });
+            assert_eq!(Ok(Some(Token::Open(Delimiter::Bracket))), p.parse_value());
+            assert_eq!(Ok(Some(Token::String(String::from("caf\u{e9} \u{1f600}")))), p.parse_value());
+            assert_eq!((1, expected), (p.line, p.character));
+        }
+
+        let mut options = ReaderOptions::new();
+        options.parser.columns = ColumnMode::Bytes;
+        let error = parse_with_options("\"\u{1f600}\" ]", &options).unwrap_err();
+        assert_eq!((1, 8), (error.line(), error.character()));
+    }
+
+    #[test]
+    fn line_ending_test() {
+        let mut p = Parser::new("a\r\n; note\r\n[1\r\n 2]\r\nb");
+        let tokens: Vec<Token> = (0..6).map(|_| p.parse_value().unwrap().unwrap()).collect();
+        assert_eq!(Token::Symbol { namespace: None, name: "b" }, tokens[5]);
+        assert_eq!((5, 1), (p.line, p.character));
+
+        let mut p = Parser::new("a\nb\r\nc\rd\n\re");
+        assert_eq!(5, p.by_ref().filter(|t| matches!(*t, Ok(Token::Symbol { .. }))).count());
+        assert_eq!((6, 1), (p.line, p.character));
+
+        assert_eq!(
+            Ok(vec!(Token::Comment(String::from(";x")), Token::Whitespace("\r\n"), Token::Nil)),
+            tokenize(";x\r\nnil"));
+    }
+
+    #[test]
+    fn parse_value_consuming_test() {
+        let input = "[1 2] trailing \"text";
+        let (value, end) = parse_value_consuming(input).unwrap();
+        assert_eq!(Value::Vector(vec!(Value::Integer(1), Value::Integer(2))), value);
+        assert_eq!(5, end);
+        assert_eq!(" trailing \"text", &input[end..]);
+
+        assert_eq!(Ok((Value::Integer(42), 4)), parse_value_consuming("  42 ; rest\n}"));
+        assert_eq!(Ok((Value::Symbol("x".into()), 1)), parse_value_consuming("x}}"));
+        assert_eq!(Ok((Value::Tagged { tag: String::from("t"), value: Box::new(Value::Nil) }, 6)), parse_value_consuming("#t nil,"));
+
+        assert_eq!("unexpected end of input", parse_value_consuming(" ; none").unwrap_err().message());
+        assert!(parse_value_consuming("[1 2").is_err());
+    }
+
+    #[test]
+    fn parse_all_test() {
+        assert_eq!(Ok(vec!(Value::Integer(1), Value::Bool(true))), parse_all("1 true"));
+        assert_eq!(
+            Ok(vec!(
+                Value::Map(vec!((Value::Keyword { namespace: None, name: "event".into(), auto_resolved: false }, Value::Integer(1)))),
+                Value::Symbol("sym".into()),
+                Value::List(vec!()),
+                Value::String(String::from("s")))),
+            parse_all("{:event 1}sym ; between\n()\"s\""));
+        assert_eq!(Ok(vec!(Value::Nil)), parse_all("  nil \n\t; done\n  "));
+        assert_eq!(Ok(vec!()), parse_all(""));
+
+        let error = parse_all("1 2 [3").unwrap_err();
+        assert_eq!("unexpected end of input, expected `]`", error.message());
+    }
+
+    #[test]
+    fn empty_input_test() {
+        for &s in &["", "   ", "; just a comment"] {
+            assert_eq!(Ok(None), Parser::new(s).parse_value());
+        }
+
+        for &s in &["", "   ", "; just a comment", " ,\n#_ 1 ; nothing kept"] {
+            assert_eq!(Ok(vec!()), parse_all(s));
+            assert_eq!(0, Values::new(s, &ReaderOptions::default()).count());
+
+            // where a single value is wanted, its absence is an error
+            assert_eq!("unexpected end of input", parse(s).unwrap_err().message());
+            assert_eq!("unexpected end of input", parse_bytes(s.as_bytes()).unwrap_err().message());
+            assert_eq!("unexpected end of input", parse_value_consuming(s).unwrap_err().message());
+            assert_eq!("unexpected end of input", validate(s).unwrap_err().message());
+            assert_eq!(None, parse_recovering(s).0);
+        }
+    }
+
+    #[test]
+    fn borrowed_symbol_test() {
+        let s = "(map inc my.ns/values)";
+        let tokens = tokenize(s).unwrap();
+
+        assert_eq!(Token::Symbol { namespace: Some("my.ns"), name: "values" }, tokens[5]);
+
+        // symbols point into the source rather than owning a copy
+        let range = s.as_bytes().as_ptr_range();
+        for token in &tokens {
+            if let Token::Symbol { name, .. } = *token {
+                assert!(range.contains(&name.as_ptr()));
+            }
+        }
+
+        assert_eq!(Ok(Value::Symbol("my.ns/values".into())), parse("my.ns/values"));
+    }
+
+    #[test]
+    fn delimiter_test() {
+        let s = "(a[]\\b){}#{}";
+        let mut p = Parser::new(s);
+        assert_eq!(Ok(Some(Token::Open(Delimiter::Paren))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: "a" })), p.parse_value());
+        assert_eq!(Ok(Some(Token::Open(Delimiter::Bracket))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Close(Delimiter::Bracket))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Character('b'))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Close(Delimiter::Paren))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Open(Delimiter::Brace))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Close(Delimiter::Brace))), p.parse_value());
+        assert_eq!(Ok(Some(Token::OpenSet)), p.parse_value());
+        assert_eq!(Ok(Some(Token::Close(Delimiter::Brace))), p.parse_value());
+        assert_eq!(Ok(None), p.parse_value());
+    }
+
+    #[test]
+    fn comment_test() {
+        let s = "; leading\nnil;trailing\n;; more\n  true ; at eof";
+        let mut p = Parser::new(s);
+        assert_eq!(Ok(Some(Token::Nil)), p.parse_value());
+        assert_eq!((4, 3), (p.line, p.character));
+        assert_eq!(Ok(Some(Token::Boolean(true))), p.parse_value());
+        assert_eq!(4, p.line);
+        assert_eq!(None, p.current_character);
+        assert_eq!(Ok(None), p.parse_value());
+
+        let s = "; only a comment\n";
+        p = Parser::new(s);
+        p.next_character();
+        assert_eq!(Some(Token::Comment(String::from("; only a comment"))), p.parse_comment());
+        assert_eq!(Some('\n'), p.current_character);
+    }
+
+    #[test]
+    fn tag_test() {
+        let s = "#inst \"x\" #my.ns/Tag[] #_";
+        let mut p = Parser::new(s);
+        assert_eq!(Ok(Some(Token::Tag(String::from("inst")))), p.parse_value());
+        assert_eq!(Ok(Some(Token::String(String::from("x")))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Tag(String::from("my.ns/Tag")))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Open(Delimiter::Bracket))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Close(Delimiter::Bracket))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Discard)), p.parse_value());
+
+        assert!(Parser::new("#a/").parse_value().is_err());
+        assert!(Parser::new("#1").parse_value().is_err());
+    }
+
+    #[test]
+    fn parse_with_options_test() {
+        let mut options = ReaderOptions::new();
+        options.register_tag("my.app/Point", |value| match value {
+            Value::Vector(ref coordinates) if coordinates.len() == 2 => Ok(Value::Map(vec!(
+                (Value::Keyword { namespace: None, name: "x".into(), auto_resolved: false }, coordinates[0].clone()),
+                (Value::Keyword { namespace: None, name: "y".into(), auto_resolved: false }, coordinates[1].clone())))),
+            _ => Err(String::from("expected [x y]")),
+        });
+
+        assert_eq!(
+            Ok(Value::Map(vec!(
+                (Value::Keyword { namespace: None, name: "x".into(), auto_resolved: false }, Value::Integer(1)),
+                (Value::Keyword { namespace: None, name: "y".into(), auto_resolved: false }, Value::Integer(2))))),
+            parse_with_options("#my.app/Point [1 2]", &options));
+
+        let error = parse_with_options("#my.app/Point [1]", &options).unwrap_err();
+        assert_eq!("#my.app/Point: expected [x y]", error.message());
+
+        // without the handler the tag is preserved
+        assert_eq!(
+            Ok(Value::Tagged {
+                tag: String::from("my.app/Point"),
+                value: Box::new(Value::Vector(vec!(Value::Integer(1), Value::Integer(2)))),
+            }),
+            parse("#my.app/Point [1 2]"));
+    }
+
+    #[test]
+    fn whitespace_test() {
+        assert!(Parser::is_whitespace(&' '));
+        assert!(Parser::is_whitespace(&'\t'));
+        assert!(Parser::is_whitespace(&','));
+        assert!(!Parser::is_whitespace(&'f'));
+    }
+
+    #[test]
+    fn reset_test() {
+        let options = ParserOptions { max_total_tokens: 6, ..ParserOptions::default() };
+        let mut p = Parser::with_options("{:a [1 2]}\n; done", options);
+
+        // stops at the token limit, before the closing `}`
+        let first: Vec<_> = p.by_ref().collect();
+        assert_eq!(((1, 10), 9), (p.position(), p.byte_offset()));
+
+        p.reset();
+        assert_eq!(((1, 0), 0), (p.position(), p.byte_offset()));
+
+        let second: Vec<_> = p.by_ref().collect();
+        assert_eq!(first, second);
+        assert_eq!(9, second.len());
+        assert!(second[8].is_err());
+    }
+
+    #[test]
+    fn position_test() {
+        let mut p = Parser::new("(a
+  \u{e9}t\u{e9} 1)");
+        assert_eq!(((1, 0), 0), (p.position(), p.byte_offset()));
+
+        p.parse_value().unwrap();
+        assert_eq!(((1, 2), 1), (p.position(), p.byte_offset()));
+
+        p.parse_value().unwrap();
+        assert_eq!(((2, 3), 5), (p.position(), p.byte_offset()));
+
+        p.parse_value().unwrap();
+        assert_eq!(((2, 7), 11), (p.position(), p.byte_offset()));
+
+        p.parse_value().unwrap();
+        p.parse_value().unwrap();
+        assert_eq!(((2, 8), 13), (p.position(), p.byte_offset()));
+        assert_eq!(Ok(None), p.parse_value());
+    }
+
+    // reads percentages such as `50%` as floats
+    struct PercentParser {
+        digits: usize,
+        percent: bool,
+        valid: bool,
+    }
+
+    impl TokenParser for PercentParser {
+        fn matches(&mut self, c: &char) -> bool {
+            match *c {
+                '0'..='9' if !self.percent => self.digits += 1,
+                '%' if (self.digits > 0) && !self.percent => self.percent = true,
+                _ => self.valid = false,
+            }
+
+            self.valid
+        }
+
+        fn get_token<'s>(&self, text: &'s str) -> Option<Token<'s>> {
+            if !self.valid || !self.percent {
+                return None;
+            }
+
+            text[..self.digits].parse::<f64>().ok().map(|f| Token::Float(f / 100.0))
+        }
+
+        fn reset(&mut self) {
+            *self = PercentParser { digits: 0, percent: false, valid: true };
+        }
+    }
+
+    #[test]
+    fn token_parser_reset_test() {
+        let mut integer = IntegerTokenParser::strict();
+        assert_eq!(Some(Token::Integer(-12)), feed(&mut integer, "-12"));
+        integer.reset();
+        assert_eq!(None, feed(&mut integer, "007"));
+        integer.reset();
+        assert_eq!(Some(Token::Integer(7)), feed(&mut integer, "7"));
+
+        let mut keyword = KeywordParser::new();
+        assert_eq!(None, feed(&mut keyword, "nope"));
+        keyword.reset();
+        assert_eq!(
+            Some(Token::Keyword { namespace: None, name: String::from("a"), auto_resolved: true }),
+            feed(&mut keyword, "::a"));
+
+        let mut nil = KeywordTokenParser::new("nil", Token::Nil);
+        assert_eq!(Some(Token::Nil), feed(&mut nil, "nil"));
+        nil.reset();
+        assert_eq!(Some(Token::Nil), feed(&mut nil, "nil"));
+    }
+
+    #[test]
+    fn add_token_parser_test() {
+        let mut p = Parser::new("[50% 12 5%%]");
+        p.add_token_parser(PercentParser { digits: 0, percent: false, valid: true });
+
+        assert_eq!(Ok(Some(Token::Open(Delimiter::Bracket))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Float(0.5))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Integer(12))), p.parse_value());
+        assert_eq!("invalid token `5%%`", p.parse_value().unwrap_err().message());
+
+        assert!(Parser::new("50%").parse_value().is_err());
+    }
+
+    #[test]
+    fn skip_whitespace_test() {
+        // ASCII runs are skipped in bulk, the no-break space and the comment
+        // a character at a time, and the counts must agree across the two
+        let mut p = Parser::new("a \t\r\n  b,\r\r\u{a0} ;c\n\u{e9}\n");
+
+        p.parse_value().unwrap();
+        assert_eq!(((2, 3), 7), (p.position(), p.byte_offset()));
+
+        p.parse_value().unwrap();
+        assert_eq!(((5, 1), 17), (p.position(), p.byte_offset()));
+        assert_eq!(Some('\u{e9}'), p.current_character);
+
+        p.parse_value().unwrap();
+        assert_eq!(((6, 0), 20), (p.position(), p.byte_offset()));
+        assert_eq!(Ok(None), p.parse_value());
+    }
+
+    #[test]
+    fn expect_eof_test() {
+        assert_eq!(Ok(()), Parser::new("").expect_eof());
+        assert_eq!(Ok(()), Parser::new(" ,\n ; comment").expect_eof());
+
+        let mut p = Parser::new("[1]");
+        assert_eq!(Ok(Some(Token::Open(Delimiter::Bracket))), p.next_token());
+        assert!(p.expect_eof().is_err());
+
+        // whitespace `next_token` leaves after a value
+        let mut p = Parser::new("1 ; one\n\t");
+        assert_eq!(Ok(Some(Token::Integer(1))), p.next_token());
+        assert_eq!(Ok(()), p.expect_eof());
+
+        let mut p = Parser::new("1\n ; one\n  :b");
+        assert_eq!(Ok(Some(Token::Integer(1))), p.parse_value());
+        let error = p.expect_eof().unwrap_err();
+        assert_eq!("unexpected trailing content", error.message());
+        assert_eq!((3, 3), (error.line(), error.character()));
+    }
+
+    #[test]
+    fn peek_character_test() {
+        let mut p = Parser::new("a\n\u{e9}");
+        assert_eq!(Some('a'), p.peek_character());
+        assert_eq!(None, p.current_character);
+
+        assert_eq!(Some('a'), p.next_character());
+        assert_eq!(Some('\n'), p.peek_character());
+        assert_eq!(Some('\n'), p.peek_character());
+        assert_eq!((1, 1, 0), (p.line, p.character, p.offset));
+
+        assert_eq!(Some('\n'), p.next_character());
+        assert_eq!(Some('\u{e9}'), p.peek_character());
+        assert_eq!((1, 2), (p.line, p.character));
+
+        assert_eq!(Some('\u{e9}'), p.next_character());
+        assert_eq!(None, p.peek_character());
+        assert_eq!((2, 1, 2), (p.line, p.character, p.offset));
+        assert_eq!(None, p.next_character());
+    }
+
+    #[test]
+    fn parse_dispatch_test() {
+        let dispatch = |s| {
+            let mut p = Parser::new(s);
+            p.next_character();
+            (p.parse_dispatch(), p.current_character)
+        };
+
+        assert_eq!((Ok(Token::OpenSet), Some('1')), dispatch("#{1}"));
+        assert_eq!((Ok(Token::Discard), Some('1')), dispatch("#_1"));
+        assert_eq!((Ok(Token::Tag(String::from("foo"))), Some(' ')), dispatch("#foo 1"));
+
+        assert_eq!(Ok(Value::Set(vec!(Value::Integer(1)))), parse("#{1}"));
+        assert_eq!(Ok(Value::Vector(vec!(Value::Integer(2)))), parse("[#_1 2]"));
+        assert_eq!(
+            Ok(Value::Tagged { tag: String::from("foo"), value: Box::new(Value::Integer(1)) }),
+            parse("#foo 1"));
+    }
+
+    #[test]
+    fn dispatch_test() {
+        let tokens = |s| tokenize(s).unwrap().into_iter().filter(|t| !matches!(*t, Token::Whitespace(_))).collect::<Vec<_>>();
+
+        assert_eq!(vec!(Token::OpenSet, Token::Close(Delimiter::Brace)), tokens("#{}"));
+        assert_eq!(vec!(Token::Discard, Token::Integer(1)), tokens("#_1"));
+        assert_eq!(vec!(Token::Tag(String::from("inst")), Token::String(String::from("x"))), tokens("#inst \"x\""));
+        assert_eq!(vec!(Token::Tag(String::from("uuid")), Token::String(String::from("x"))), tokens("#uuid \"x\""));
+        assert_eq!(vec!(Token::Tag(String::from("my.app/Point")), Token::Integer(1)), tokens("#my.app/Point 1"));
+        assert_eq!(vec!(Token::NamespacedMap(String::from("ns")), Token::Open(Delimiter::Brace), Token::Close(Delimiter::Brace)),
+                   tokens("#:ns{}"));
+
+        for &(s, message) in &[("#@x", "unsupported reader macro `#@`"), ("#;x", "unsupported reader macro `#;`"),
+                               ("#(inc %)", "unsupported reader macro `#(`"), ("# 1", "unsupported reader macro `#`"),
+                               ("#", "unsupported reader macro `#`")] {
+            let error = tokenize(s).unwrap_err();
+            assert_eq!(message, error.message());
+            assert_eq!((1, 1), (error.line(), error.character()));
+        }
+    }
+
+    #[test]
+    fn symbolic_value_test() {
+        assert_eq!(Ok(Value::Float(f64::INFINITY)), parse("##Inf"));
+        assert_eq!(Ok(Value::Float(f64::NEG_INFINITY)), parse("##-Inf"));
+        assert!(parse("##NaN").unwrap().as_f64().unwrap().is_nan());
+
+        match parse("[##NaN ##Inf]").unwrap().as_vector() {
+            Some(&[Value::Float(nan), Value::Float(inf)]) => assert!(nan.is_nan() && (inf == f64::INFINITY)),
+            items => panic!("expected two floats, found {:?}", items),
+        }
+
+        assert_eq!("##-Inf", parse("##-Inf").unwrap().to_string());
+        assert_eq!("unknown symbolic value `##Foo`", parse("##Foo").unwrap_err().message());
+        assert_eq!("unknown symbolic value `##inf`", parse("[##inf]").unwrap_err().message());
+        assert_eq!("unknown symbolic value `##`", parse("## Inf").unwrap_err().message());
+    }
+
+    #[test]
+    fn comma_tokens_test() {
+        let s = "[1,, 2 ,3]";
+        assert_eq!(
+            Ok(vec!(Token::Open(Delimiter::Bracket), Token::Integer(1), Token::Whitespace(",, "), Token::Integer(2),
+                    Token::Whitespace(" ,"), Token::Integer(3), Token::Close(Delimiter::Bracket))),
+            tokenize(s));
+
+        let options = ParserOptions { comma_tokens: true, ..ParserOptions::default() };
+        let mut p = Parser::with_options(s, options.clone());
+        let mut tokens = vec!();
+        while let Some(token) = p.next_token().unwrap() {
+            tokens.push(token);
+        }
+        assert_eq!(
+            vec!(Token::Open(Delimiter::Bracket), Token::Integer(1), Token::Comma, Token::Comma, Token::Whitespace(" "),
+                 Token::Integer(2), Token::Whitespace(" "), Token::Comma, Token::Integer(3), Token::Close(Delimiter::Bracket)),
+            tokens);
+
+        let mut p = Parser::with_options(s, options.clone());
+        let values: Vec<Token> = (0..5).map(|_| p.parse_value().unwrap().unwrap()).collect();
+        assert_eq!(Token::Integer(3), values[3]);
+        assert_eq!(Ok(None), p.parse_value());
+
+        let mut reader_options = ReaderOptions::default();
+        reader_options.parser = options;
+        assert_eq!(parse(s), parse_with_options(s, &reader_options));
+    }
+
+    #[test]
+    fn parse_whitespace_test() {
+        let s = " ";
+        let mut p = Parser::new(s);
+        p.next_character();
+        assert_eq!(Some(Token::Whitespace(s)), p.parse_whitespace());
+
+        let s = " \n ";
+        let mut p = Parser::new(s);
+        p.next_character();
+        assert_eq!(Some(Token::Whitespace(s)), p.parse_whitespace());
         assert_eq!(2, p.line);
         assert_eq!(1, p.character);
     }
 
+    #[test]
+    fn whitespace_slice_test() {
+        let s = "{:a 1,\n    :b [2\t3]}\r\n";
+        let whitespace: Vec<&str> = tokenize(s).unwrap().into_iter()
+            .filter_map(|token| if let Token::Whitespace(ws) = token { Some(ws) } else { None })
+            .collect();
+        assert_eq!(vec!(" ", ",\n    ", " ", "\t", "\r\n"), whitespace);
+
+        // each run is exactly the source it spans
+        let mut p = Parser::new(s);
+        let mut rebuilt = String::new();
+
+        while let Some((token, span)) = p.next_spanned().unwrap() {
+            if let Token::Whitespace(ws) = token {
+                assert_eq!(&s[span.start..span.end], ws);
+            }
+
+            rebuilt.push_str(&s[span.start..span.end]);
+        }
+
+        assert_eq!(s, rebuilt);
+    }
+
     #[test]
     fn value_parser_test() {
-        assert_eq!(Some(Token::Nil), Parser::new(&String::from("nil")).parse_value());
-        assert_eq!(Some(Token::Boolean(true)), Parser::new(&String::from("true")).parse_value());
-        assert_eq!(Some(Token::Boolean(false)), Parser::new(&String::from("false")).parse_value());
+        assert_eq!(Ok(Some(Token::Nil)), Parser::new("nil").parse_value());
+        assert_eq!(Ok(Some(Token::Boolean(true))), Parser::new("true").parse_value());
+        assert_eq!(Ok(Some(Token::Boolean(false))), Parser::new("false").parse_value());
 
         let s = "alskdjflsajkfsldf";
-        assert_eq!(Some(Token::Symbol(s.chars().collect())), Parser::new(&String::from(s)).parse_value());
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: s })), Parser::new(s).parse_value());
 
         let s = "+123";
-        assert_eq!(None, Parser::new(&String::from(s)).parse_value());
+        assert_eq!(Ok(Some(Token::Integer(123))), Parser::new(s).parse_value());
+
+        let s = "-17";
+        assert_eq!(Ok(Some(Token::Integer(-17))), Parser::new(s).parse_value());
+
+        let s = "123456789012345678901234567890N";
+        assert_eq!(Ok(Some(Token::BigInteger(String::from(&s[..s.len() - 1])))), Parser::new(s).parse_value());
+
+        let s = "N";
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: s })), Parser::new(s).parse_value());
+
+        let s = "123Nx";
+        assert!(Parser::new(s).parse_value().is_err());
+
+        let s = "42";
+        assert_eq!(Ok(Some(Token::Integer(42))), Parser::new(s).parse_value());
+
+        let s = "-1.5e3";
+        assert_eq!(Ok(Some(Token::Float(-1.5e3))), Parser::new(s).parse_value());
+
+        let s = "1.5M";
+        assert_eq!(Ok(Some(Token::BigDecimal(String::from("1.5")))), Parser::new(s).parse_value());
+
+        let s = "M";
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: s })), Parser::new(s).parse_value());
+
+        let s = ".5";
+        assert!(Parser::new(s).parse_value().is_err());
+
+        let s = "-";
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: s })), Parser::new(s).parse_value());
 
         let s = "f123/123";
-        assert_eq!(None, Parser::new(&String::from(s)).parse_value());
+        assert!(Parser::new(s).parse_value().is_err());
 
         let s = "+#:123/#";
-        assert_eq!(None, Parser::new(&String::from(s)).parse_value());
+        assert!(Parser::new(s).parse_value().is_err());
 
         let s = "\"Foobar\"";
-        assert_eq!(Some(Token::String(s.chars().collect())), Parser::new(&String::from(s)).parse_value());
+        assert_eq!(Ok(Some(Token::String(String::from("Foobar")))), Parser::new(s).parse_value());
 
         let s = "\"Foo\\\"bar\"";
-        assert_eq!(Some(Token::String(s.chars().collect())), Parser::new(&String::from(s)).parse_value());
+        assert_eq!(Ok(Some(Token::String(String::from("Foo\"bar")))), Parser::new(s).parse_value());
 
+        // strings end at their closing quote, whatever follows
         let s = "\"Foo\"bar\"";
-        assert_eq!(None, Parser::new(&String::from(s)).parse_value());
+        let mut p = Parser::new(s);
+        assert_eq!(Ok(Some(Token::String(String::from("Foo")))), p.parse_value());
+        assert_eq!(Ok(Some(Token::Symbol { namespace: None, name: "bar" })), p.parse_value());
+        assert!(p.parse_value().is_err());
 
         let s = "#_+123";
-        assert_eq!(Some(Token::Discard(s.chars().collect())), Parser::new(&String::from(s)).parse_value());
+        let mut p = Parser::new(s);
+        assert_eq!(Ok(Some(Token::Discard)), p.parse_value());
+        assert_eq!(Ok(Some(Token::Integer(123))), p.parse_value());
     }
 }
 
@@ -0,0 +1,190 @@
+use alloc::collections::BTreeMap;
+
+use prelude::*;
+use {Interner, Value};
+
+/// Turns the form following a tag such as `#my.app/Point` into a value.
+///
+/// An `Err` message is reported as a parse error at the tagged form.
+pub trait TagHandler {
+    fn handle(&self, value: Value) -> Result<Value, String>;
+}
+
+impl<F> TagHandler for F where F: Fn(Value) -> Result<Value, String> {
+    fn handle(&self, value: Value) -> Result<Value, String> {
+        self(value)
+    }
+}
+
+/// The unit `ParseError` and `Span` columns are counted in.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum ColumnMode {
+    /// One column per Unicode scalar value.
+    #[default]
+    Characters,
+    /// One column per byte of UTF-8.
+    Bytes,
+    /// One column per UTF-16 code unit, as used by the Language Server Protocol.
+    Utf16,
+}
+
+impl ColumnMode {
+    pub(crate) fn width(self, c: char) -> i64 {
+        match self {
+            ColumnMode::Characters => 1,
+            ColumnMode::Bytes => c.len_utf8() as i64,
+            ColumnMode::Utf16 => c.len_utf16() as i64,
+        }
+    }
+}
+
+/// What to do with a map that has the same key more than once.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum DuplicateKeyPolicy {
+    /// Fail, as the EDN spec requires.
+    #[default]
+    Error,
+    /// Keep the last value given for the key, at the place of the first.
+    LastWins,
+    /// Keep the first value given for the key.
+    FirstWins,
+}
+
+/// What to do with a set that has the same element more than once.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum DuplicateElementPolicy {
+    /// Fail, as the EDN spec requires.
+    #[default]
+    Error,
+    /// Keep the element once, where it first appears.
+    Dedup,
+}
+
+/// How maps are held once read.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum MapBacking {
+    /// A `Value::Map`, whose entries `Value::get` searches one by one.
+    #[default]
+    Pairs,
+    /// A `Value::IndexedMap`, which finds keys by their hash and keeps the
+    /// entries in the order they were written.
+    #[cfg(feature = "indexmap")]
+    Indexed,
+}
+
+impl MapBacking {
+    pub(crate) fn map(self, pairs: Vec<(Value, Value)>) -> Value {
+        match self {
+            MapBacking::Pairs => Value::Map(pairs),
+            #[cfg(feature = "indexmap")]
+            MapBacking::Indexed => Value::IndexedMap(pairs.into_iter().collect()),
+        }
+    }
+}
+
+/// Settings for splitting EDN text into tokens.
+#[derive(Debug,Clone)]
+pub struct ParserOptions {
+    pub columns: ColumnMode,
+    /// How deeply collections may nest before reading fails, which keeps
+    /// untrusted input from exhausting the stack.
+    pub max_depth: usize,
+    /// Most items a single collection may hold, counting each map entry as
+    /// one item.
+    pub max_collection_len: usize,
+    /// Most tokens, whitespace and comments aside, read from one input.
+    pub max_total_tokens: usize,
+    /// Rejects integers with leading zeros, such as `007`, as the EDN spec
+    /// does, rather than reading them as octal.
+    pub strict_numbers: bool,
+    /// Has `next_token` return each comma as a `Token::Comma` instead of as
+    /// part of the surrounding whitespace. Values are read the same either way.
+    pub comma_tokens: bool,
+    /// Reads `;` and the rest of its line as a comment, as EDN does. Without
+    /// comments `;` is instead a symbol character like `*`, so `;foo` is a
+    /// symbol, for input that uses `;` as data.
+    pub allow_comments: bool,
+    /// How maps with a repeated key are read.
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// How sets with a repeated element are read.
+    pub duplicate_elements: DuplicateElementPolicy,
+    /// How maps are held once read, with duplicate keys already dealt with.
+    pub map_backing: MapBacking,
+    /// Shares symbol and keyword names between the values read.
+    pub interner: Option<Interner>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> ParserOptions {
+        ParserOptions {
+            columns: ColumnMode::default(),
+            max_depth: 256,
+            max_collection_len: usize::MAX,
+            max_total_tokens: usize::MAX,
+            strict_numbers: false,
+            comma_tokens: false,
+            allow_comments: true,
+            duplicate_keys: DuplicateKeyPolicy::default(),
+            duplicate_elements: DuplicateElementPolicy::default(),
+            map_backing: MapBacking::default(),
+            interner: None,
+        }
+    }
+}
+
+/// Settings for reading values out of EDN text.
+///
+/// Tags without a registered handler, other than the built-in `#inst` and
+/// `#uuid`, are kept as `Value::Tagged`.
+#[derive(Default)]
+pub struct ReaderOptions {
+    pub parser: ParserOptions,
+    tag_handlers: BTreeMap<String, Box<dyn TagHandler>>,
+}
+
+impl ReaderOptions {
+    pub fn new() -> ReaderOptions {
+        ReaderOptions::default()
+    }
+
+    /// Registers `handler` for `tag`, given without the leading `#`. This
+    /// replaces any earlier handler for the same tag, built-ins included.
+    pub fn register_tag<H>(&mut self, tag: &str, handler: H) where H: TagHandler + 'static {
+        self.tag_handlers.insert(String::from(tag), Box::new(handler));
+    }
+
+    pub fn tag_handler(&self, tag: &str) -> Option<&dyn TagHandler> {
+        self.tag_handlers.get(tag).map(|h| &**h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_mode_test() {
+        assert_eq!(ColumnMode::Characters, ParserOptions::default().columns);
+
+        for &(c, bytes, utf16) in &[('a', 1, 1), ('\u{e9}', 2, 1), ('\u{20ac}', 3, 1), ('\u{1f600}', 4, 2)] {
+            assert_eq!(1, ColumnMode::Characters.width(c));
+            assert_eq!(bytes, ColumnMode::Bytes.width(c));
+            assert_eq!(utf16, ColumnMode::Utf16.width(c));
+        }
+    }
+
+    #[test]
+    fn register_tag_test() {
+        let mut options = ReaderOptions::new();
+        assert!(options.tag_handler("double").is_none());
+
+        options.register_tag("double", |value| match value {
+            Value::Integer(i) => Ok(Value::Integer(i * 2)),
+            _ => Err(String::from("expected an integer")),
+        });
+
+        let handler = options.tag_handler("double").unwrap();
+        assert_eq!(Ok(Value::Integer(4)), handler.handle(Value::Integer(2)));
+        assert_eq!(Err(String::from("expected an integer")), handler.handle(Value::Nil));
+    }
+}
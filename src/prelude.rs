@@ -0,0 +1,5 @@
+// The parts of the standard prelude that come from `alloc`, for modules to
+// import so that they build without `std` as well.
+pub use alloc::boxed::Box;
+pub use alloc::string::{String, ToString};
+pub use alloc::vec::Vec;
@@ -0,0 +1,139 @@
+use prelude::*;
+use Value;
+
+/// Layout settings for `pretty_print`.
+pub struct PrettyOptions {
+    /// Spaces added for each level of nesting.
+    pub indent: usize,
+    /// Lists, vectors and sets longer than this are split one item per line.
+    pub width: usize,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> PrettyOptions {
+        PrettyOptions { indent: 2, width: 80 }
+    }
+}
+
+/// Renders `value` as indented, multi-line EDN.
+///
+/// Maps put each key/value pair on its own line; other collections stay on one
+/// line while they fit within `options.width`. Empty collections and scalars
+/// are rendered as by `Display`.
+pub fn pretty_print(value: &Value, options: &PrettyOptions) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, options, 0, 0);
+    out
+}
+
+// non-empty maps never share a line with their siblings
+fn is_multiline(value: &Value) -> bool {
+    match *value {
+        Value::Map(ref pairs) => !pairs.is_empty(),
+        #[cfg(feature = "indexmap")]
+        Value::IndexedMap(ref map) => !map.is_empty(),
+        Value::Tagged { ref value, .. } => is_multiline(value),
+        _ => false,
+    }
+}
+
+fn write_indent(out: &mut String, options: &PrettyOptions, depth: usize) {
+    for _ in 0..(depth * options.indent) {
+        out.push(' ');
+    }
+}
+
+fn write_value(out: &mut String, value: &Value, options: &PrettyOptions, depth: usize, column: usize) {
+    match *value {
+        Value::Map(ref pairs) if !pairs.is_empty() => write_entries(out, pairs.iter().map(|(k, v)| (k, v)), options, depth),
+        #[cfg(feature = "indexmap")]
+        Value::IndexedMap(ref map) if !map.is_empty() => write_entries(out, map.iter(), options, depth),
+        Value::List(ref items) => write_items(out, "(", items, ")", options, depth, column),
+        Value::Vector(ref items) => write_items(out, "[", items, "]", options, depth, column),
+        Value::Set(ref items) => write_items(out, "#{", items, "}", options, depth, column),
+        Value::Tagged { ref tag, ref value } => {
+            let prefix = format!("#{} ", tag);
+
+            out.push_str(&prefix);
+            write_value(out, value, options, depth, column + prefix.len());
+        },
+        ref value => out.push_str(&value.to_string()),
+    }
+}
+
+fn write_entries<'a, I>(out: &mut String, entries: I, options: &PrettyOptions, depth: usize) where I: Iterator<Item = (&'a Value, &'a Value)> {
+    out.push_str("{\n");
+
+    for (key, value) in entries {
+        let key = key.to_string();
+
+        write_indent(out, options, depth + 1);
+        out.push_str(&key);
+        out.push(' ');
+        write_value(out, value, options, depth + 1, ((depth + 1) * options.indent) + key.len() + 1);
+        out.push('\n');
+    }
+
+    write_indent(out, options, depth);
+    out.push('}');
+}
+
+fn write_items(out: &mut String, open: &str, items: &[Value], close: &str, options: &PrettyOptions, depth: usize, column: usize) {
+    let compact: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+    let compact = format!("{}{}{}", open, compact.join(" "), close);
+
+    if items.is_empty() || ((column + compact.len() <= options.width) && !items.iter().any(is_multiline)) {
+        out.push_str(&compact);
+        return;
+    }
+
+    out.push_str(open);
+    out.push('\n');
+
+    for item in items {
+        write_indent(out, options, depth + 1);
+        write_value(out, item, options, depth + 1, (depth + 1) * options.indent);
+        out.push('\n');
+    }
+
+    write_indent(out, options, depth);
+    out.push_str(close);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parse;
+
+    #[test]
+    fn pretty_print_test() {
+        let value = parse("{:name \"edn\" :deps {:a [1 2] :b #{}} :tags [x y]}").unwrap();
+
+        let options = PrettyOptions { indent: 2, width: 80 };
+        assert_eq!(
+            "{\n  :name \"edn\"\n  :deps {\n    :a [1 2]\n    :b #{}\n  }\n  :tags [x y]\n}",
+            pretty_print(&value, &options));
+
+        let options = PrettyOptions { indent: 4, width: 80 };
+        assert_eq!(
+            "{\n    :name \"edn\"\n    :deps {\n        :a [1 2]\n        :b #{}\n    }\n    :tags [x y]\n}",
+            pretty_print(&value, &options));
+
+        assert_eq!(Ok(value.clone()), parse(&pretty_print(&value, &options)));
+    }
+
+    #[test]
+    fn wrap_test() {
+        let options = PrettyOptions { indent: 2, width: 16 };
+
+        assert_eq!("{}", pretty_print(&parse("{}").unwrap(), &options));
+        assert_eq!("[]", pretty_print(&parse("[]").unwrap(), &options));
+        assert_eq!("(1 2 3)", pretty_print(&parse("(1 2 3)").unwrap(), &options));
+        assert_eq!(
+            "[\n  :alpha\n  [:beta :gamma]\n]",
+            pretty_print(&parse("[:alpha [:beta :gamma]]").unwrap(), &options));
+        assert_eq!(
+            "#{\n  {\n    :a 1\n  }\n}",
+            pretty_print(&parse("#{{:a 1}}").unwrap(), &PrettyOptions::default()));
+    }
+}
@@ -0,0 +1,259 @@
+use decode::Utf8Decoder;
+use prelude::*;
+use reader::Reader;
+use {error_after, position_at, Parser, ParseError, ReaderOptions, Value};
+
+/// Reads top-level values out of input that arrives in pieces, such as from
+/// a socket. A piece may end anywhere, even part way through a character.
+///
+/// A value is complete once input following it arrives, or as soon as it
+/// ends in a closing delimiter or quote; until then `12` might yet be `123`.
+/// Errors are likewise reported once input following them arrives, or by
+/// `finish`. Limits such as `ParserOptions::max_total_tokens` apply to the
+/// whole of the input, however it is split.
+///
+/// Each piece has the value in progress read again from its start, so a value
+/// arriving in many small pieces costs time quadratic in its length. Pieces
+/// that grow along with the value keep the cost linear.
+pub struct PushParser {
+    options: ReaderOptions,
+    decoder: Utf8Decoder,
+    // decoded input not yet read as part of a value
+    buffer: String,
+    // the position in the whole input of the start of `buffer`
+    offset: usize,
+    line: i64,
+    character: i64,
+    // tokens of the values read so far, counted against the options' limit
+    tokens: usize,
+}
+
+impl PushParser {
+    pub fn new() -> PushParser {
+        PushParser::with_options(ReaderOptions::default())
+    }
+
+    pub fn with_options(options: ReaderOptions) -> PushParser {
+        PushParser { options, decoder: Utf8Decoder::new(), buffer: String::new(), offset: 0, line: 1, character: 1, tokens: 0 }
+    }
+
+    /// Adds `bytes` to the input, returning the values they complete.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<Vec<Value>, ParseError> {
+        self.push(bytes)?;
+        self.read(false, usize::MAX)
+    }
+
+    /// Ends the input, returning the values still held back.
+    pub fn finish(mut self) -> Result<Vec<Value>, ParseError> {
+        self.finish_decoding()?;
+        self.read(true, usize::MAX)
+    }
+
+    // adds `bytes` to the input without reading any values out of it
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
+        match self.decoder.push(bytes, &mut self.buffer) {
+            Ok(()) => Ok(()),
+            Err(offset) => Err(self.error_at_end(format!("invalid UTF-8 at byte {}", offset))),
+        }
+    }
+
+    // the length of the decoded input not yet read as part of a value
+    #[cfg(feature = "std")]
+    pub(crate) fn pending_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    // the first value still to be read, if it is complete, leaving any input
+    // after it unread
+    #[cfg(feature = "std")]
+    pub(crate) fn read_one(&mut self) -> Result<Option<Value>, ParseError> {
+        Ok(self.read(false, 1)?.pop())
+    }
+
+    // like `read_one` at the end of the input, where having no value is an
+    // error, as for `parse`
+    #[cfg(feature = "std")]
+    pub(crate) fn finish_one(&mut self) -> Result<Value, ParseError> {
+        self.finish_decoding()?;
+
+        match self.read(true, 1)?.pop() {
+            Some(value) => Ok(value),
+            None => {
+                let mut parser = Parser::with_options(&self.buffer, self.options.parser.clone());
+                parser.skip_whitespace();
+                Err(self.relocate(parser.error(String::from("unexpected end of input"))))
+            },
+        }
+    }
+
+    // fails at the first token left in the input, then drops the lines read
+    // so far; no comment can still be open before the last line break
+    #[cfg(feature = "std")]
+    pub(crate) fn expect_eof(&mut self, finished: bool) -> Result<(), ParseError> {
+        if finished {
+            self.finish_decoding()?;
+        }
+
+        Parser::with_options(&self.buffer, self.options.parser.clone()).expect_eof().map_err(|error| self.relocate(error))?;
+
+        let end = self.buffer.rfind('\n').map_or(0, |i| i + 1);
+        self.consume(end);
+        Ok(())
+    }
+
+    // an error just past the input decoded so far
+    pub(crate) fn error_at_end(&self, message: String) -> ParseError {
+        self.relocate(error_after(&self.buffer, message, self.options.parser.columns))
+    }
+
+    fn finish_decoding(&self) -> Result<(), ParseError> {
+        match self.decoder.finish() {
+            Ok(()) => Ok(()),
+            Err(offset) => Err(self.error_at_end(format!("invalid UTF-8 at byte {}", offset))),
+        }
+    }
+
+    // reads out up to `limit` values
+    fn read(&mut self, finished: bool, limit: usize) -> Result<Vec<Value>, ParseError> {
+        let mut values = vec!();
+
+        while values.len() < limit {
+            let (result, end, token_end, tokens) = {
+                let mut parser = Parser::with_options(&self.buffer, self.options.parser.clone());
+                parser.tokens = self.tokens;
+                let mut reader = Reader::new(parser, &self.options);
+                let result = reader.read();
+                (result, reader.parser().offset, reader.parser().token_end, reader.parser().tokens)
+            };
+
+            // only what more input cannot change is final
+            let open_ended = !finished && (end == self.buffer.len());
+
+            match result {
+                Ok(Some(value)) => {
+                    // a value is over once anything follows its last token
+                    let followed = token_end < self.buffer.len();
+                    let closed = self.buffer[..token_end].ends_with([')', ']', '}', '"']);
+
+                    if !finished && !followed && !closed {
+                        return Ok(values);
+                    }
+
+                    // a comment running to the end of the buffer may go on
+                    // in the next piece, so is read again along with it
+                    self.consume(if open_ended { token_end } else { end });
+                    self.tokens = tokens;
+                    values.push(value);
+                },
+                Ok(None) => return Ok(values),
+                Err(_) if open_ended => return Ok(values),
+                Err(error) => return Err(self.relocate(error)),
+            }
+        }
+
+        Ok(values)
+    }
+
+    // drops the first `end` bytes of the buffer, which have been read
+    fn consume(&mut self, end: usize) {
+        // where the lexer puts what is left, which depends on the character
+        // after a `\r` for whether it ends a line
+        match position_at(&self.buffer, end, self.options.parser.columns) {
+            (1, character) => self.character += character - 1,
+            (line, character) => {
+                self.line += line - 1;
+                self.character = character;
+            },
+        }
+
+        self.buffer.drain(..end);
+        self.offset += end;
+    }
+
+    // moves an error positioned within the buffer to its place in the input
+    fn relocate(&self, error: ParseError) -> ParseError {
+        error.moved(self.offset, self.line - 1, self.character - 1)
+    }
+}
+
+impl Default for PushParser {
+    fn default() -> PushParser {
+        PushParser::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {parse, parse_all_with_options, ColumnMode};
+
+    #[test]
+    fn push_parser_test() {
+        let mut parser = PushParser::new();
+        assert_eq!(Ok(vec!()), parser.feed(b"{:a [1 2"));
+        assert_eq!(Ok(vec!()), parser.feed(b"] :b \"x\xc3"));
+        assert_eq!(Ok(vec!(parse("{:a [1 2] :b \"x\u{e9}y\"}").unwrap())), parser.feed(b"\xa9y\"}"));
+        assert_eq!(Ok(vec!()), parser.feed(b" "));
+
+        // a number may go on in the next piece
+        assert_eq!(Ok(vec!()), parser.feed(b"; one\n12"));
+        assert_eq!(Ok(vec!(Value::Integer(123), Value::Nil)), parser.feed(b"3 nil [4"));
+        assert_eq!(Ok(vec!(Value::vector(vec!(Value::Integer(4))))), parser.feed(b"] 5"));
+        assert_eq!(Ok(vec!(Value::Integer(5))), parser.finish());
+
+        // whitespace after a value is enough to end it
+        assert_eq!(Ok(vec!(Value::Integer(42))), PushParser::new().feed(b"42\n"));
+        assert_eq!(Ok(vec!(parse(":a").unwrap())), PushParser::new().feed(b":a "));
+
+        let mut parser = PushParser::new();
+        assert_eq!(Ok(vec!(Value::Integer(1))), parser.feed(b"1 ; one"));
+        assert_eq!(Ok(vec!()), parser.feed(b" 2\n3"));
+        assert_eq!(Ok(vec!(Value::Integer(3))), parser.finish());
+    }
+
+    #[test]
+    fn push_parser_error_test() {
+        let mut parser = PushParser::new();
+        assert_eq!(Ok(vec!(Value::Integer(1), Value::Integer(2))), parser.feed(b"1\n2 (]"));
+
+        let error = parser.finish().unwrap_err();
+        assert_eq!("unexpected closing delimiter `]`, expected `)`", error.message());
+        assert_eq!((2, 4), (error.line(), error.character()));
+
+        let mut parser = PushParser::new();
+        assert!(parser.feed(b"[1 2").is_ok());
+        assert_eq!("unexpected end of input, expected `]`", parser.finish().unwrap_err().message());
+
+        let mut parser = PushParser::new();
+        assert_eq!("invalid UTF-8 at byte 3", parser.feed(b"[1 \xff]").unwrap_err().message());
+
+        // the token limit is for the whole input
+        let mut options = ReaderOptions::default();
+        options.parser.max_total_tokens = 4;
+        let mut parser = PushParser::with_options(options);
+        assert_eq!(Ok(vec!(Value::vector(vec!(Value::Integer(1))))), parser.feed(b"[1] "));
+        assert_eq!(Ok(vec!(Value::Integer(2))), parser.feed(b"2 "));
+        let error = parser.feed(b"3 ").unwrap_err();
+        assert_eq!("input exceeds 4 tokens", error.message());
+        assert_eq!((1, 7), (error.line(), error.character()));
+
+        // positions are counted as the lexer counts them, whatever the pieces
+        for &(s, columns) in &[("1\r2 3\r(]", ColumnMode::Characters), ("1\r\n2\r\n(]", ColumnMode::Characters),
+                               ("\"\u{1f600}\u{1f600}\" 1 (]", ColumnMode::Utf16), ("\"\u{1f600}\u{1f600}\" 1 (]", ColumnMode::Bytes)] {
+            let options = || {
+                let mut options = ReaderOptions::default();
+                options.parser.columns = columns;
+                options
+            };
+            let expected = parse_all_with_options(s, &options()).unwrap_err();
+
+            for split in (0..=s.len()).filter(|&i| s.is_char_boundary(i)) {
+                let mut parser = PushParser::with_options(options());
+                let (first, rest) = s.as_bytes().split_at(split);
+                let error = parser.feed(first).and_then(|_| parser.feed(rest))
+                    .and_then(|_| parser.finish()).unwrap_err();
+                assert_eq!((expected.line(), expected.character()), (error.line(), error.character()), "{:?} at {}", s, split);
+            }
+        }
+    }
+}
@@ -0,0 +1,885 @@
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+
+use prelude::*;
+use value::item_hash;
+use {Delimiter, DuplicateElementPolicy, DuplicateKeyPolicy, Instant, Parser, ParseError, ReaderOptions, Span, Token, Uuid, Value};
+
+#[derive(Clone,Copy,PartialEq)]
+enum Collection {
+    List,
+    Vector,
+    Map,
+    Set,
+}
+
+impl Collection {
+    fn delimiter(&self) -> Delimiter {
+        match *self {
+            Collection::List => Delimiter::Paren,
+            Collection::Vector => Delimiter::Bracket,
+            Collection::Map | Collection::Set => Delimiter::Brace,
+        }
+    }
+}
+
+// the hashes and positions of the keys or elements of a collection being
+// read, so that a repeated one is found without comparing it to every other
+#[derive(Default)]
+struct Seen(BTreeSet<(u64, usize)>);
+
+impl Seen {
+    // the position of an item equal to `item`, among those added from `items`
+    fn find(&self, items: &[Value], item: &Value) -> Option<usize> {
+        let hash = item_hash(item);
+        self.0.range((hash, 0)..=(hash, usize::MAX)).map(|&(_, index)| index).find(|&index| items[index] == *item)
+    }
+
+    fn insert(&mut self, item: &Value, index: usize) {
+        self.0.insert((item_hash(item), index));
+    }
+
+    fn remove(&mut self, item: &Value, index: usize) {
+        self.0.remove(&(item_hash(item), index));
+    }
+}
+
+/// Builds values out of the tokens of a `Lexer`, the layer `parse` and
+/// `Values` are built on. Tokens that take no part in a value, such as
+/// whitespace, comments and discarded forms, are skipped.
+pub struct Reader<'a> {
+    parser: Parser<'a>,
+    options: &'a ReaderOptions,
+    depth: usize,
+    line: i64,
+    character: i64,
+    start: usize,
+    // whether errors are collected in `errors` and read past where possible
+    recovering: bool,
+    errors: Vec<ParseError>,
+    // whether the items of lists and vectors are dropped once read, leaving
+    // them empty; those of maps and sets are compared, so are always kept
+    discarding: bool,
+}
+
+impl<'a> Reader<'a> {
+    /// Reads from `parser`, with tags handled and collections read as
+    /// `options` says. `options.parser` should be what the parser was made
+    /// with, so that both agree on the limits and policies they share.
+    pub fn new(parser: Parser<'a>, options: &'a ReaderOptions) -> Reader<'a> {
+        Reader { parser, options, depth: 0, line: 1, character: 0, start: 0, recovering: false, errors: vec!(), discarding: false }
+    }
+
+    // a reader for checking input rather than using its values, which keeps
+    // as little of them as it can; tag handlers would see the emptied lists
+    // and vectors, so there should be none
+    pub(crate) fn discarding(parser: Parser<'a>, options: &'a ReaderOptions) -> Reader<'a> {
+        Reader { discarding: true, ..Reader::new(parser, options) }
+    }
+
+    // a reader that reads past the errors it can, for `take_errors` to return
+    pub(crate) fn recovering(parser: Parser<'a>, options: &'a ReaderOptions) -> Reader<'a> {
+        Reader { recovering: true, ..Reader::new(parser, options) }
+    }
+
+    pub(crate) fn take_errors(&mut self) -> Vec<ParseError> {
+        self.errors.split_off(0)
+    }
+
+    // notes an error to carry on past, or fails with it if not recovering
+    fn recover(&mut self, error: ParseError) -> Result<(), ParseError> {
+        if !self.recovering {
+            return Err(error);
+        }
+
+        self.errors.push(error);
+        Ok(())
+    }
+
+    // fails with an error that leaves nowhere sensible to carry on from
+    fn give_up(&mut self, error: ParseError) -> ParseError {
+        self.recovering = false;
+        error
+    }
+
+    // the next token, or the error of a bad one that can be left out, the
+    // parser having moved past it
+    fn next_or_skippable(&mut self) -> Result<Result<Option<Token<'a>>, ParseError>, ParseError> {
+        match self.next_token() {
+            Err(error) if (self.parser.line, self.parser.character) == (self.line, self.character) => {
+                Err(self.give_up(error))
+            },
+            result => Ok(result),
+        }
+    }
+
+    /// The lexer, positioned just past the last value read.
+    pub fn parser(&self) -> &Parser<'a> {
+        &self.parser
+    }
+
+    pub fn parser_mut(&mut self) -> &mut Parser<'a> {
+        &mut self.parser
+    }
+
+    /// Reads the next complete value, or `None` once the input is exhausted.
+    pub fn read(&mut self) -> Result<Option<Value>, ParseError> {
+        loop {
+            let result = match self.next_or_skippable()? {
+                Ok(Some(Token::Close(found))) => Err(self.error(format!("unexpected closing delimiter `{}`", found.close()))),
+                Ok(Some(token)) => self.read_form(token).map(Some),
+                Ok(None) => Ok(None),
+                Err(error) => Err(error),
+            };
+
+            match result {
+                Err(error) => self.recover(error)?,
+                result => return result,
+            }
+        }
+    }
+
+    // an error at the start of the last token
+    pub(crate) fn error(&self, message: String) -> ParseError {
+        ParseError::new(message, self.line, self.character)
+            .with_span(Span { start: self.start, end: self.start, line: self.line, character: self.character })
+    }
+
+    // an error about the form read since the last token started at `span`
+    fn form_error(&self, message: String, span: Span) -> ParseError {
+        ParseError::new(message, span.line, span.character).with_span(Span { end: self.parser.token_end, ..span })
+    }
+
+    // the next token that takes part in a value, remembering where it starts
+    pub(crate) fn next_token(&mut self) -> Result<Option<Token<'a>>, ParseError> {
+        loop {
+            self.parser.skip_whitespace();
+
+            self.line = self.parser.line;
+            self.character = self.parser.character;
+            self.start = self.parser.offset;
+
+            if self.parser.current_character.is_none() {
+                return Ok(None);
+            }
+
+            match self.parser.parse_value()? {
+                Some(Token::Discard) => {
+                    match self.next_token()? {
+                        token @ Some(Token::Close(_)) | token @ None => {
+                            self.recover(self.error(String::from("expected a form to discard after `#_`")))?;
+                            return Ok(token);
+                        },
+                        Some(token) => {
+                            if let Err(error) = self.read_form(token) {
+                                self.recover(error)?;
+                            }
+                        },
+                    }
+                },
+                Some(token) => return Ok(Some(token)),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    fn read_form(&mut self, token: Token<'a>) -> Result<Value, ParseError> {
+        match token {
+            Token::Open(Delimiter::Paren) => self.read_collection(Collection::List),
+            Token::Open(Delimiter::Bracket) => self.read_collection(Collection::Vector),
+            Token::Open(Delimiter::Brace) => self.read_collection(Collection::Map),
+            Token::OpenSet => self.read_collection(Collection::Set),
+            Token::Tag(tag) => self.read_tagged(tag),
+            Token::NamespacedMap(namespace) => self.read_namespaced_map(namespace),
+            token => Value::from_token(token, self.options.parser.interner.as_ref()).ok_or_else(|| self.error(String::from("invalid value"))),
+        }
+    }
+
+    pub(crate) fn read_tagged(&mut self, tag: String) -> Result<Value, ParseError> {
+        let value = match self.next_token()? {
+            Some(Token::Close(_)) | None => {
+                return Err(self.error(format!("expected a form after tag #{}", tag)));
+            },
+            Some(token) => self.read_form(token)?,
+        };
+
+        if let Some(handler) = self.options.tag_handler(&tag) {
+            return handler.handle(value).map_err(|message| self.error(format!("#{}: {}", tag, message)));
+        }
+
+        match (&tag[..], value) {
+            ("inst", Value::String(s)) => Instant::parse(&s)
+                .map(Value::Instant)
+                .ok_or_else(|| self.error(format!("#inst: invalid RFC-3339 timestamp {:?}", s))),
+            ("inst", _) => Err(self.error(String::from("#inst expects a string"))),
+            ("uuid", Value::String(s)) => Uuid::parse(&s).map(Value::Uuid).map_err(|position| {
+                match s.chars().nth(position) {
+                    Some(c) => self.error(format!("#uuid: invalid character {:?} at position {} in {:?}", c, position, s)),
+                    None => self.error(format!("#uuid: unexpected end of string at position {} in {:?}", position, s)),
+                }
+            }),
+            ("uuid", _) => Err(self.error(String::from("#uuid expects a string"))),
+            (_, value) => Ok(Value::Tagged { tag, value: Box::new(value) }),
+        }
+    }
+
+    fn read_namespaced_map(&mut self, namespace: String) -> Result<Value, ParseError> {
+        let span = Span { start: self.start, end: self.start, line: self.line, character: self.character };
+
+        let pairs = match self.next_token()? {
+            Some(Token::Open(Delimiter::Brace)) => match self.read_collection(Collection::Map)? {
+                Value::Map(pairs) => pairs,
+                #[cfg(feature = "indexmap")]
+                Value::IndexedMap(map) => map.into_iter().collect(),
+                _ => unreachable!("maps are read as maps"),
+            },
+            _ => return Err(self.error(format!("expected a map after #:{}", namespace))),
+        };
+
+        let mut qualified: Vec<(Value, Value)> = vec!();
+
+        for (key, value) in pairs {
+            let key = qualify(key, &namespace);
+
+            match (qualified.iter().position(|(k, _)| *k == key), self.options.parser.duplicate_keys) {
+                (None, _) => qualified.push((key, value)),
+                (Some(_), DuplicateKeyPolicy::Error) => {
+                    return Err(self.form_error(format!("duplicate map key {}", key), span));
+                },
+                (Some(index), DuplicateKeyPolicy::LastWins) => qualified[index].1 = value,
+                (Some(_), DuplicateKeyPolicy::FirstWins) => {},
+            }
+        }
+
+        Ok(self.options.parser.map_backing.map(qualified))
+    }
+
+    fn read_collection(&mut self, collection: Collection) -> Result<Value, ParseError> {
+        if self.depth >= self.options.parser.max_depth {
+            let error = self.error(format!("collections nested deeper than {} levels", self.options.parser.max_depth));
+            return Err(self.give_up(error));
+        }
+
+        let discarding = self.discarding;
+        self.discarding = discarding && ((collection == Collection::List) || (collection == Collection::Vector));
+
+        self.depth += 1;
+        let result = self.read_items(collection);
+        self.depth -= 1;
+
+        self.discarding = discarding;
+        result
+    }
+
+    fn read_items(&mut self, collection: Collection) -> Result<Value, ParseError> {
+        let delimiter = collection.delimiter();
+        let mut items = vec!();
+        // items read but not kept, when discarding
+        let mut dropped = 0;
+        // whether the next item is the value of a map entry being dropped
+        let mut skip = false;
+        // where the next item goes instead, as the new value of a repeated key
+        let mut replace = None;
+        // the keys of a map, or the elements of a set, kept so far
+        let mut seen = Seen::default();
+
+        loop {
+            let token = match self.next_or_skippable()? {
+                Ok(Some(Token::Close(found))) => {
+                    if found != delimiter {
+                        // when recovering, the wrong delimiter still ends the collection
+                        self.recover(self.error(format!(
+                            "unexpected closing delimiter `{}`, expected `{}`", found.close(), delimiter.close())))?;
+                    }
+
+                    break;
+                },
+                Ok(None) => {
+                    self.recover(self.error(format!("unexpected end of input, expected `{}`", delimiter.close())))?;
+                    break;
+                },
+                Ok(Some(token)) => Ok(token),
+                Err(error) => Err(error),
+            };
+
+            let span = Span { start: self.start, end: self.start, line: self.line, character: self.character };
+
+            // a key and its value are one item of a map
+            let count = if collection == Collection::Map { (items.len() + dropped) / 2 } else { items.len() + dropped };
+
+            if count >= self.options.parser.max_collection_len {
+                let error = self.error(format!("collection exceeds {} items", self.options.parser.max_collection_len));
+                return Err(self.give_up(error));
+            }
+
+            let item = match token.and_then(|token| self.read_form(token)) {
+                Ok(item) => item,
+                Err(error) => {
+                    self.recover(error)?;
+
+                    // drop the whole of a bad map entry
+                    if skip || replace.is_some() {
+                        skip = false;
+                        replace = None;
+                    } else if collection == Collection::Map {
+                        if items.len() % 2 == 0 {
+                            skip = true;
+                        } else if let Some(key) = items.pop() {
+                            seen.remove(&key, items.len());
+                        }
+                    }
+
+                    continue;
+                },
+            };
+
+            if skip {
+                skip = false;
+                continue;
+            }
+
+            if self.discarding {
+                dropped += 1;
+                continue;
+            }
+
+            if let Some(index) = replace.take() {
+                items[index] = item;
+                continue;
+            }
+
+            if (collection == Collection::Map) && (items.len() % 2 == 0) {
+                if let Some(index) = seen.find(&items, &item) {
+                    match self.options.parser.duplicate_keys {
+                        DuplicateKeyPolicy::Error => {
+                            self.recover(self.form_error(format!("duplicate map key {}", item), span))?;
+                            skip = true;
+                        },
+                        DuplicateKeyPolicy::LastWins => replace = Some(index + 1),
+                        DuplicateKeyPolicy::FirstWins => skip = true,
+                    }
+
+                    continue;
+                }
+
+                seen.insert(&item, items.len());
+            }
+
+            if collection == Collection::Set {
+                if seen.find(&items, &item).is_some() {
+                    if self.options.parser.duplicate_elements == DuplicateElementPolicy::Error {
+                        self.recover(self.form_error(format!("duplicate set element {}", item), span))?;
+                    }
+
+                    continue;
+                }
+
+                seen.insert(&item, items.len());
+            }
+
+            items.push(item);
+        }
+
+        match collection {
+            Collection::List => Ok(Value::List(items)),
+            Collection::Vector => Ok(Value::Vector(items)),
+            Collection::Set => Ok(Value::Set(items)),
+            Collection::Map => {
+                if items.len() % 2 != 0 {
+                    self.recover(self.error(String::from("map literal contains an odd number of forms")))?;
+                    items.pop();
+                }
+
+                let mut pairs = vec!();
+                let mut items = items.into_iter();
+
+                while let (Some(key), Some(value)) = (items.next(), items.next()) {
+                    pairs.push((key, value));
+                }
+
+                Ok(self.options.parser.map_backing.map(pairs))
+            },
+        }
+    }
+}
+
+// Applies the namespace of `#:ns{...}` to a key. Keys that already have a
+// namespace are left alone, except that `_` marks a key meant to stay bare.
+pub(crate) fn qualify(key: Value, namespace: &str) -> Value {
+    match key {
+        Value::Keyword { namespace: None, name, auto_resolved: false } => {
+            Value::Keyword { namespace: Some(Arc::from(namespace)), name, auto_resolved: false }
+        },
+        Value::Keyword { namespace: Some(ref bare), ref name, auto_resolved: false } if &**bare == "_" => {
+            Value::Keyword { namespace: None, name: name.clone(), auto_resolved: false }
+        },
+        Value::Symbol(ref name) if name.starts_with("_/") => Value::Symbol(Arc::from(&name[2..])),
+        Value::Symbol(ref name) if !name.contains('/') => Value::Symbol(Arc::from(format!("{}/{}", namespace, name))),
+        key => key,
+    }
+}
+
+/// The top-level values of a document, read one at a time.
+///
+/// Iteration ends at the end of the input or after the first error.
+pub struct Values<'a> {
+    reader: Reader<'a>,
+    failed: bool,
+}
+
+impl<'a> Values<'a> {
+    pub fn new(input: &'a str, options: &'a ReaderOptions) -> Values<'a> {
+        Values { reader: Reader::new(Parser::with_options(input, options.parser.clone()), options), failed: false }
+    }
+}
+
+impl<'a> Iterator for Values<'a> {
+    type Item = Result<Value, ParseError>;
+
+    fn next(&mut self) -> Option<Result<Value, ParseError>> {
+        if self.failed {
+            return None;
+        }
+
+        let result = self.reader.read();
+        self.failed = result.is_err();
+        result.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {parse, parse_recovering};
+
+    fn read(s: &str) -> Result<Option<Value>, ParseError> {
+        Reader::new(Parser::new(s), &ReaderOptions::default()).read()
+    }
+
+    #[test]
+    fn read_test() {
+        let options = ReaderOptions::default();
+        let source = "nil :a \"b\" #_skipped 1";
+        let mut reader = Reader::new(Parser::new(source), &options);
+
+        assert_eq!(Ok(Some(Value::Nil)), reader.read());
+        assert_eq!(Ok(Some(Value::Keyword { namespace: None, name: "a".into(), auto_resolved: false })), reader.read());
+        assert_eq!(Ok(Some(Value::String(String::from("b")))), reader.read());
+        assert_eq!(Ok(Some(Value::Integer(1))), reader.read());
+        assert_eq!(Ok(None), reader.read());
+        assert_eq!(Ok(None), reader.read());
+
+        let source = "nil 12a";
+        let mut reader = Reader::new(Parser::new(source), &options);
+        assert_eq!(Ok(Some(Value::Nil)), reader.read());
+
+        let error = reader.read().unwrap_err();
+        assert_eq!("invalid token `12a`", error.message());
+        assert_eq!((1, 5), (error.line(), error.character()));
+    }
+
+    #[test]
+    fn max_depth_test() {
+        let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+        assert!(read(&nested(256)).is_ok());
+
+        let error = read(&nested(100_000)).unwrap_err();
+        assert_eq!("collections nested deeper than 256 levels", error.message());
+        assert_eq!((1, 257), (error.line(), error.character()));
+
+        let mut options = ReaderOptions::default();
+        options.parser.max_depth = 2;
+        assert!(Reader::new(Parser::new("[(1) {:a #{}}]"), &options).read().is_err());
+        assert_eq!(
+            Ok(Some(Value::Vector(vec!(Value::List(vec!(Value::Integer(1))))))),
+            Reader::new(Parser::new("[(1)]"), &options).read());
+    }
+
+    #[test]
+    fn size_limit_test() {
+        let mut options = ReaderOptions::default();
+        options.parser.max_collection_len = 3;
+
+        assert_eq!(3, Reader::new(Parser::new("[1 2 3]"), &options).read().unwrap().unwrap().as_vector().unwrap().len());
+        assert!(Reader::new(Parser::new("{:a 1}"), &options).read().is_ok());
+
+        let error = Reader::new(Parser::new("[1 2 3 4 5]"), &options).read().unwrap_err();
+        assert_eq!("collection exceeds 3 items", error.message());
+        assert_eq!((1, 8), (error.line(), error.character()));
+        assert!(Reader::new(Parser::new("{:a 1 :b 2 :c 3}"), &options).read().is_ok());
+
+        let error = Reader::new(Parser::new("{:a 1 :b 2 :c 3 :d 4}"), &options).read().unwrap_err();
+        assert_eq!("collection exceeds 3 items", error.message());
+        assert_eq!((1, 17), (error.line(), error.character()));
+
+        let mut options = ReaderOptions::default();
+        options.parser.max_total_tokens = 4;
+
+        let mut reader = Reader::new(Parser::with_options("[1 2] ; four\n[3]", options.parser.clone()), &options);
+        assert!(reader.read().is_ok());
+
+        let error = reader.read().unwrap_err();
+        assert_eq!("input exceeds 4 tokens", error.message());
+        assert_eq!((2, 1), (error.line(), error.character()));
+    }
+
+    #[test]
+    fn namespaced_map_test() {
+        let qualified = |namespace: Option<&str>, name: &str| {
+            Value::Keyword { namespace: namespace.map(Arc::from), name: Arc::from(name), auto_resolved: false }
+        };
+
+        assert_eq!(
+            Ok(Some(Value::Map(vec!(
+                (qualified(Some("person"), "name"), Value::String(String::from("x"))),
+                (qualified(Some("person"), "age"), Value::Integer(1)))))),
+            read("#:person{:name \"x\" :age 1}"));
+        assert_eq!(
+            Ok(Some(Value::Map(vec!(
+                (qualified(Some("other"), "id"), Value::Integer(1)),
+                (Value::Symbol("person/sym".into()), Value::Integer(2)),
+                (Value::Integer(3), Value::Integer(3)))))),
+            read("#:person {:other/id 1 sym 2 3 3}"));
+        assert_eq!(
+            Ok(Some(Value::Map(vec!(
+                (qualified(None, "key"), Value::Integer(1)),
+                (Value::Symbol("bare".into()), Value::Integer(2)))))),
+            read("#:person{:_/key 1 _/bare 2}"));
+
+        assert_eq!("duplicate map key :a/b", read("#:a{:b 1 :a/b 2}").unwrap_err().message());
+        assert_eq!("expected a map after #:a", read("#:a [1 2]").unwrap_err().message());
+        assert_eq!("invalid map namespace `#:a/b`", read("#:a/b{}").unwrap_err().message());
+    }
+
+    #[test]
+    fn vector_test() {
+        assert_eq!(Ok(Some(Value::Vector(vec!()))), read("[]"));
+        assert_eq!(Ok(Some(Value::Vector(vec!()))), read("[ , ]"));
+        assert_eq!(
+            Ok(Some(Value::Vector(vec!(Value::Integer(1), Value::Integer(2), Value::Integer(3))))),
+            read("[1 2 3]"));
+        assert_eq!(
+            Ok(Some(Value::Vector(vec!(Value::Integer(1), Value::Integer(2), Value::Integer(3))))),
+            read("[1,2 ,3]"));
+        assert_eq!(
+            Ok(Some(Value::Vector(vec!(
+                Value::Vector(vec!(Value::Integer(1))),
+                Value::Vector(vec!(Value::Integer(2), Value::Integer(3))))))),
+            read("[[1] [2 3]]"));
+        assert_eq!(
+            Ok(Some(Value::Vector(vec!(
+                Value::Nil,
+                Value::String(String::from("a b")),
+                Value::Keyword { namespace: None, name: "k".into(), auto_resolved: false },
+                Value::Float(1.5),
+                Value::Character('c'),
+                Value::Symbol("sym".into()))))),
+            read("[nil \"a b\" :k 1.5 \\c sym]"));
+
+        let error = read("[1 2").unwrap_err();
+        assert_eq!("unexpected end of input, expected `]`", error.message());
+        assert_eq!((1, 4), (error.line(), error.character()));
+
+        let error = read("[[1]\n [2").unwrap_err();
+        assert_eq!("unexpected end of input, expected `]`", error.message());
+        assert_eq!((2, 3), (error.line(), error.character()));
+    }
+
+    #[test]
+    fn list_test() {
+        assert_eq!(Ok(Some(Value::List(vec!()))), read("()"));
+        assert_eq!(
+            Ok(Some(Value::List(vec!(Value::Symbol("+".into()), Value::Integer(1), Value::Integer(2))))),
+            read("(+ 1, 2)"));
+        assert_eq!(
+            Ok(Some(Value::List(vec!(Value::List(vec!(Value::List(vec!(Value::List(vec!(Value::Nil)))))))))),
+            read("((((nil))))"));
+        assert_eq!(
+            Ok(Some(Value::List(vec!(
+                Value::Symbol("f".into()),
+                Value::Vector(vec!(Value::Integer(1), Value::List(vec!()))))))),
+            read("(f [1 ()])"));
+
+        let error = read("(1 2]").unwrap_err();
+        assert_eq!("unexpected closing delimiter `]`, expected `)`", error.message());
+        assert_eq!((1, 5), (error.line(), error.character()));
+
+        let error = read("[(]").unwrap_err();
+        assert_eq!("unexpected closing delimiter `]`, expected `)`", error.message());
+
+        let error = read("(1").unwrap_err();
+        assert_eq!("unexpected end of input, expected `)`", error.message());
+    }
+
+    fn keyword(name: &str) -> Value {
+        Value::Keyword { namespace: None, name: Arc::from(name), auto_resolved: false }
+    }
+
+    #[test]
+    fn map_test() {
+        assert_eq!(Ok(Some(Value::Map(vec!()))), read("{}"));
+        assert_eq!(
+            Ok(Some(Value::Map(vec!((keyword("a"), Value::Integer(1)), (keyword("b"), Value::Integer(2)))))),
+            read("{:a 1 :b 2}"));
+        assert_eq!(
+            Ok(Some(Value::Map(vec!(
+                (keyword("a"), Value::Map(vec!((Value::Vector(vec!(Value::Integer(1))), Value::Nil)))),
+                (keyword("b"), Value::List(vec!())))))),
+            read("{:a {[1] nil}, :b ()}"));
+
+        let error = read("{:a}").unwrap_err();
+        assert_eq!("map literal contains an odd number of forms", error.message());
+        assert_eq!((1, 4), (error.line(), error.character()));
+
+        let error = read("{:a 1 :b 2 :a 3}").unwrap_err();
+        assert!(error.message().starts_with("duplicate map key"));
+        assert_eq!((1, 12), (error.line(), error.character()));
+
+        // a key equal to an earlier value is fine
+        assert_eq!(
+            Ok(Some(Value::Map(vec!((keyword("a"), keyword("b")), (keyword("b"), keyword("a")))))),
+            read("{:a :b :b :a}"));
+
+        let error = read("{[1 2] 1 [1 2] 2}").unwrap_err();
+        assert!(error.message().starts_with("duplicate map key"));
+    }
+
+    #[test]
+    fn duplicate_key_policy_test() {
+        let read_with = |policy, s| {
+            let mut options = ReaderOptions::default();
+            options.parser.duplicate_keys = policy;
+            Reader::new(Parser::new(s), &options).read()
+        };
+
+        let error = read_with(DuplicateKeyPolicy::Error, "{:a 1 :a 2}").unwrap_err();
+        assert_eq!("duplicate map key :a", error.message());
+        assert_eq!((1, 7), (error.line(), error.character()));
+
+        assert_eq!(
+            Ok(Some(Value::Map(vec!((keyword("a"), Value::Integer(2)))))),
+            read_with(DuplicateKeyPolicy::LastWins, "{:a 1 :a 2}"));
+        assert_eq!(
+            Ok(Some(Value::Map(vec!((keyword("a"), Value::Integer(1)))))),
+            read_with(DuplicateKeyPolicy::FirstWins, "{:a 1 :a 2}"));
+
+        // the key keeps its first place
+        assert_eq!(
+            Ok(Some(Value::Map(vec!((keyword("a"), Value::Integer(3)), (keyword("b"), Value::Integer(2)))))),
+            read_with(DuplicateKeyPolicy::LastWins, "{:a 1 :b 2 :a 3}"));
+        assert_eq!(
+            Ok(Some(Value::Map(vec!((Value::Keyword { namespace: Some("n".into()), name: "a".into(), auto_resolved: false }, Value::Integer(2)))))),
+            read_with(DuplicateKeyPolicy::LastWins, "#:n{:a 1 :n/a 2}"));
+
+        // a key dropped along with its bad value is no longer a duplicate
+        let (value, errors) = parse_recovering("{:a 1 :b 2a :b 3 :a 4}");
+        assert_eq!(Some(parse("{:a 1 :b 3}").unwrap()), value);
+        assert_eq!(vec!("invalid token `2a`", "duplicate map key :a"), errors.iter().map(|e| e.message()).collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn map_backing_test() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        use MapBacking;
+
+        let mut options = ReaderOptions::default();
+        options.parser.map_backing = MapBacking::Indexed;
+        let read_indexed = |s| Reader::new(Parser::new(s), &options).read().unwrap().unwrap();
+
+        let value = read_indexed("{:c 1 :a {:x [2]} :b 3}");
+        let keys: Vec<&Value> = value.as_indexed_map().unwrap().keys().collect();
+        assert_eq!(vec!(&keyword("c"), &keyword("a"), &keyword("b")), keys);
+        assert_eq!("{:c 1 :a {:x [2]} :b 3}", value.to_string());
+
+        assert_eq!(Some(&Value::Integer(3)), value.get(&keyword("b")));
+        assert_eq!(None, value.get(&keyword("d")));
+        assert!(value.get(&keyword("a")).unwrap().as_indexed_map().is_some());
+        assert_eq!(Some(&Value::Integer(2)), value.get_path_str("a/x/0"));
+
+        // equal to the same map held as pairs, in any order
+        assert_eq!(read("{:a {:x [2]} :b 3 :c 1}").unwrap().unwrap(), value);
+        assert_eq!(read_indexed("{:b 3 :a {:x [2]} :c 1}"), value);
+
+        let hash = |value: &Value| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&read("{:a {:x [2]} :b 3 :c 1}").unwrap().unwrap()), hash(&value));
+
+        let value = read_indexed("#:n{:a 1 :b 2}");
+        assert_eq!(Some(&Value::Integer(2)), value.get(&Value::Keyword { namespace: Some("n".into()), name: "b".into(), auto_resolved: false }));
+    }
+
+    #[test]
+    fn discard_test() {
+        assert_eq!(Ok(Some(Value::Vector(vec!(Value::Integer(1), Value::Integer(3))))), read("[1 #_2 3]"));
+        assert_eq!(Ok(Some(Value::Vector(vec!(Value::Integer(1), Value::Integer(3))))), read("[1 #_ [1 2 3] 3]"));
+        assert_eq!(Ok(Some(Value::Vector(vec!(Value::Integer(1))))), read("[1 #_{:a #{2}}]"));
+        assert_eq!(Ok(Some(Value::Integer(3))), read("#_ #_ 1 2 3"));
+        assert_eq!(Ok(Some(Value::List(vec!()))), read("(#_#_ a b)"));
+        assert_eq!(Ok(None), read("#_ discarded"));
+
+        let error = read("[1 #_]").unwrap_err();
+        assert_eq!("expected a form to discard after `#_`", error.message());
+        assert_eq!((1, 6), (error.line(), error.character()));
+
+        let error = read("#_").unwrap_err();
+        assert_eq!("expected a form to discard after `#_`", error.message());
+
+        let error = read("#_ #_ 1").unwrap_err();
+        assert_eq!("expected a form to discard after `#_`", error.message());
+    }
+
+    #[test]
+    fn inst_test() {
+        let instant = Instant::parse("1985-04-12T23:20:50Z").unwrap();
+        assert_eq!(Ok(Some(Value::Instant(instant))), read("#inst \"1985-04-12T23:20:50Z\""));
+
+        let instant = Instant::parse("1985-04-12T23:20:50.52Z").unwrap();
+        assert_eq!(Ok(Some(Value::Vector(vec!(Value::Instant(instant))))), read("[#inst\"1985-04-12T23:20:50.52Z\"]"));
+
+        let error = read("#inst \"not-a-date\"").unwrap_err();
+        assert_eq!("#inst: invalid RFC-3339 timestamp \"not-a-date\"", error.message());
+        assert_eq!((1, 7), (error.line(), error.character()));
+
+        let error = read("#inst 1985").unwrap_err();
+        assert_eq!("#inst expects a string", error.message());
+
+        let error = read("[#inst]").unwrap_err();
+        assert_eq!("expected a form after tag #inst", error.message());
+    }
+
+    #[test]
+    fn uuid_test() {
+        let uuid = Uuid::parse("f81d4fae-7dec-11d0-a765-00a0c91e6bf6").unwrap();
+        assert_eq!(Ok(Some(Value::Uuid(uuid))), read("#uuid \"f81d4fae-7dec-11d0-a765-00a0c91e6bf6\""));
+        assert_eq!(Ok(Some(Value::Uuid(uuid))), read("#uuid \"F81D4FAE-7DEC-11D0-A765-00A0C91E6BF6\""));
+
+        let error = read("#uuid \"f81d4fae-7dec-11d0-a765\"").unwrap_err();
+        assert_eq!("#uuid: unexpected end of string at position 23 in \"f81d4fae-7dec-11d0-a765\"", error.message());
+
+        let error = read("#uuid \"f81d4fae-7dec-11d0-z765-00a0c91e6bf6\"").unwrap_err();
+        assert_eq!("#uuid: invalid character 'z' at position 19 in \"f81d4fae-7dec-11d0-z765-00a0c91e6bf6\"", error.message());
+
+        let error = read("#uuid :a").unwrap_err();
+        assert_eq!("#uuid expects a string", error.message());
+    }
+
+    #[test]
+    fn tagged_test() {
+        let mut options = ReaderOptions::new();
+        options.register_tag("inst", |value| Ok(Value::Tagged { tag: String::from("raw"), value: Box::new(value) }));
+
+        let source = "#inst \"whenever\"";
+        let mut reader = Reader::new(Parser::new(source), &options);
+        assert_eq!(
+            Ok(Some(Value::Tagged { tag: String::from("raw"), value: Box::new(Value::String(String::from("whenever"))) })),
+            reader.read());
+
+        assert_eq!(
+            Ok(Some(Value::Tagged { tag: String::from("unknown"), value: Box::new(Value::Map(vec!())) })),
+            read("#unknown {}"));
+        assert_eq!(
+            Ok(Some(Value::Tagged {
+                tag: String::from("a"),
+                value: Box::new(Value::Tagged { tag: String::from("b"), value: Box::new(Value::Nil) }),
+            })),
+            read("#a #b nil"));
+
+        // tags without a handler need no registering
+        assert_eq!(
+            Ok(Some(Value::Tagged { tag: String::from("foo/bar"), value: Box::new(read("[1 2]").unwrap().unwrap()) })),
+            read("#foo/bar [1 2]"));
+        assert_eq!(
+            Ok(Some(Value::Tagged { tag: String::from("my.ns/Thing"), value: Box::new(read("{:a 1}").unwrap().unwrap()) })),
+            read("#my.ns/Thing {:a 1}"));
+
+        for (s, message, character) in &[("#foo/bar", "expected a form after tag #foo/bar", 8),
+                                         ("[#foo/bar]", "expected a form after tag #foo/bar", 10),
+                                         ("#foo/ 1", "invalid tag `#foo/`", 1)] {
+            let error = read(s).unwrap_err();
+            assert_eq!((*message, 1, *character), (error.message(), error.line(), error.character()));
+        }
+    }
+
+    #[test]
+    fn lexer_and_reader_test() {
+        use Lexer;
+
+        let source = "[1 :a] ; one\n#_ 2 \"b\"";
+
+        let tokens: Vec<Token> = Lexer::new(source).collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            vec!(Token::Open(Delimiter::Bracket), Token::Integer(1), Token::Whitespace(" "),
+                 Token::Keyword { namespace: None, name: String::from("a"), auto_resolved: false },
+                 Token::Close(Delimiter::Bracket), Token::Whitespace(" "), Token::Comment(String::from("; one")),
+                 Token::Whitespace("\n"), Token::Discard, Token::Whitespace(" "), Token::Integer(2), Token::Whitespace(" "),
+                 Token::String(String::from("b"))),
+            tokens);
+
+        let options = ReaderOptions::default();
+        let mut reader = Reader::new(Lexer::new(source), &options);
+        assert_eq!(Ok(Some(Value::vector(vec!(Value::Integer(1), keyword("a"))))), reader.read());
+        assert_eq!(Ok(Some(Value::String(String::from("b")))), reader.read());
+        assert_eq!(Ok(None), reader.read());
+        assert_eq!(source.len(), reader.parser().byte_offset());
+    }
+
+    #[test]
+    fn values_test() {
+        let options = ReaderOptions::default();
+
+        let mut values = Values::new("1 [2] ]", &options);
+        assert_eq!(Some(Ok(Value::Integer(1))), values.next());
+        assert_eq!(Some(Ok(Value::Vector(vec!(Value::Integer(2))))), values.next());
+        assert!(values.next().unwrap().is_err());
+        assert_eq!(None, values.next());
+
+        assert_eq!(0, Values::new(" ; nothing\n", &options).count());
+    }
+
+    #[test]
+    fn unbalanced_test() {
+        let error = read("\n  }").unwrap_err();
+        assert_eq!("unexpected closing delimiter `}`", error.message());
+        assert_eq!((2, 3), (error.line(), error.character()));
+
+        let error = read("[1 ) 2]").unwrap_err();
+        assert_eq!("unexpected closing delimiter `)`, expected `]` at line 1, character 4", error.to_string());
+
+        // a closer is never read as part of a symbol
+        let error = read("[abc)]").unwrap_err();
+        assert_eq!((1, 5), (error.line(), error.character()));
+    }
+
+    #[test]
+    fn set_test() {
+        assert_eq!(Ok(Some(Value::Set(vec!()))), read("#{}"));
+        assert_eq!(
+            Ok(Some(Value::Set(vec!(keyword("a"), keyword("b"), keyword("c"))))),
+            read("#{:a :b, :c}"));
+        assert_eq!(
+            Ok(Some(Value::Vector(vec!(Value::Integer(1), Value::Set(vec!(Value::Set(vec!()), Value::Integer(2))))))),
+            read("[1 #{#{} 2}]"));
+
+        let error = read("#{1 1}").unwrap_err();
+        assert_eq!("duplicate set element 1", error.message());
+        assert_eq!((1, 5), (error.line(), error.character()));
+
+        let mut options = ReaderOptions::default();
+        options.parser.duplicate_elements = DuplicateElementPolicy::Dedup;
+        assert_eq!(
+            Ok(Some(Value::Set(vec!(Value::Integer(1), Value::Integer(2))))),
+            Reader::new(Parser::new("#{1 2 1}"), &options).read());
+
+        let error = read("#{1 2]").unwrap_err();
+        assert_eq!("unexpected closing delimiter `]`, expected `}`", error.message());
+
+        let error = read("#{1").unwrap_err();
+        assert_eq!("unexpected end of input, expected `}`", error.message());
+    }
+}
@@ -0,0 +1,83 @@
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use Value;
+
+/// Serializes into serde's data model, which has no room for some of EDN's
+/// distinctions:
+///
+/// * keywords become strings with their leading `:`, e.g. `":ns/name"`, and
+///   symbols become plain strings, so both are indistinguishable from strings
+/// * lists, vectors and sets all become sequences
+/// * big integers and big decimals become strings of their digits, and
+///   ratios strings such as `"22/7"`
+/// * `#inst` and `#uuid` values become strings in their EDN text form
+/// * other tagged values become a single-entry map from `"#tag"` to the value
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Value::Nil => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(b),
+            Value::Integer(i) => serializer.serialize_i64(i),
+            Value::Float(f) => serializer.serialize_f64(f),
+            Value::BigInteger(ref s) | Value::BigDecimal(ref s) | Value::String(ref s) => serializer.serialize_str(s),
+            Value::Symbol(ref s) => serializer.serialize_str(s),
+            Value::Character(c) => serializer.serialize_char(c),
+            Value::Keyword { .. } | Value::Ratio { .. } => serializer.serialize_str(&self.to_string()),
+            Value::List(ref items) | Value::Vector(ref items) | Value::Set(ref items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+
+                seq.end()
+            },
+            Value::Map(ref pairs) => {
+                let mut map = serializer.serialize_map(Some(pairs.len()))?;
+
+                for (key, value) in pairs {
+                    map.serialize_entry(key, value)?;
+                }
+
+                map.end()
+            },
+            #[cfg(feature = "indexmap")]
+            Value::IndexedMap(ref entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+
+                map.end()
+            },
+            Value::Instant(ref instant) => serializer.serialize_str(&instant.to_string()),
+            Value::Uuid(ref uuid) => serializer.serialize_str(&uuid.to_string()),
+            Value::Tagged { ref tag, ref value } => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(&format!("#{}", tag), value)?;
+                map.end()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use parse;
+
+    #[test]
+    fn serialize_test() {
+        let value = parse("{:name \"edn\" :ns/tags #{sym} :items [1 2.5 nil (true)] \"big\" 10N}").unwrap();
+        assert_eq!(
+            r#"{":name":"edn",":ns/tags":["sym"],":items":[1,2.5,null,[true]],"big":"10"}"#,
+            serde_json::to_string(&value).unwrap());
+
+        let value = parse("[#inst \"1985-04-12T23:20:50.52Z\" #point [1 2] \\a]").unwrap();
+        assert_eq!(
+            r##"["1985-04-12T23:20:50.52Z",{"#point":[1,2]},"a"]"##,
+            serde_json::to_string(&value).unwrap());
+    }
+}
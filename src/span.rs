@@ -0,0 +1,12 @@
+/// Where a token sits in the source text.
+///
+/// `start` and `end` are byte offsets, `end` being exclusive, so the token's
+/// text is `&source[span.start..span.end]`. `line` and `character` give the
+/// 1-based position of its first character, counted as set by `ColumnMode`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: i64,
+    pub character: i64,
+}
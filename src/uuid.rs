@@ -0,0 +1,94 @@
+use core::fmt;
+
+/// A UUID as read from a `#uuid` tagged literal.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub struct Uuid([u8; 16]);
+
+impl Uuid {
+    pub fn from_bytes(bytes: [u8; 16]) -> Uuid {
+        Uuid(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    /// Parses the canonical `8-4-4-4-12` hex layout, upper or lower case. On
+    /// failure returns the position of the first bad character, which is the
+    /// length of `s` when it ends early.
+    pub fn parse(s: &str) -> Result<Uuid, usize> {
+        let mut bytes = [0; 16];
+        let mut digits = 0;
+        let mut position = 0;
+
+        for (i, c) in s.chars().enumerate() {
+            position = i + 1;
+
+            if (i == 8) || (i == 13) || (i == 18) || (i == 23) {
+                if c != '-' {
+                    return Err(i);
+                }
+            } else if i >= 36 {
+                return Err(i);
+            } else {
+                match c.to_digit(16) {
+                    Some(d) => {
+                        bytes[digits / 2] |= (d as u8) << (if digits % 2 == 0 { 4 } else { 0 });
+                        digits += 1;
+                    },
+                    None => return Err(i),
+                }
+            }
+        }
+
+        if position < 36 {
+            return Err(position);
+        }
+
+        Ok(Uuid(bytes))
+    }
+}
+
+/// The canonical lower case `8-4-4-4-12` form.
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if (i == 4) || (i == 6) || (i == 8) || (i == 10) {
+                write!(f, "-")?;
+            }
+
+            write!(f, "{:02x}", b)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prelude::*;
+
+    #[test]
+    fn parse_test() {
+        let uuid = Uuid::parse("f81d4fae-7dec-11d0-a765-00a0c91e6bf6").unwrap();
+        assert_eq!(
+            &[0xf8, 0x1d, 0x4f, 0xae, 0x7d, 0xec, 0x11, 0xd0, 0xa7, 0x65, 0x00, 0xa0, 0xc9, 0x1e, 0x6b, 0xf6],
+            uuid.as_bytes());
+
+        assert_eq!(Ok(uuid), Uuid::parse("F81D4FAE-7DEC-11D0-A765-00A0C91E6BF6"));
+        assert_eq!(uuid, Uuid::from_bytes(*uuid.as_bytes()));
+        assert_eq!("f81d4fae-7dec-11d0-a765-00a0c91e6bf6", uuid.to_string());
+    }
+
+    #[test]
+    fn invalid_test() {
+        assert_eq!(Err(9), Uuid::parse("f81d4fae-gdec-11d0-a765-00a0c91e6bf6"));
+        assert_eq!(Err(8), Uuid::parse("f81d4fae77dec-11d0-a765-00a0c91e6bf6"));
+        assert_eq!(Err(23), Uuid::parse("f81d4fae-7dec-11d0-a765"));
+        assert_eq!(Err(18), Uuid::parse("f81d4fae-7dec-11d0a765-00a0c91e6bf6"));
+        assert_eq!(Err(36), Uuid::parse("f81d4fae-7dec-11d0-a765-00a0c91e6bf6a"));
+        assert_eq!(Err(0), Uuid::parse(""));
+        assert_eq!(Err(2), Uuid::parse("f8\u{e9}d4fae-7dec-11d0-a765-00a0c91e6bf6"));
+    }
+}
@@ -0,0 +1,1269 @@
+use alloc::sync::Arc;
+use alloc::vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FromIterator;
+use core::mem;
+use core::ops::Index;
+use core::slice;
+use core::str::FromStr;
+
+#[cfg(feature = "indexmap")]
+use indexmap::map::{self as index_map, IndexMap};
+
+use prelude::*;
+use {parse, Instant, Interner, ParseError, Token, Uuid, CHARACTER_NAMES};
+
+#[derive(Debug,Clone)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Integer(i64),
+    BigInteger(String),
+    Float(f64),
+    BigDecimal(String),
+    // in lowest terms, with a denominator greater than 1
+    Ratio { numerator: i64, denominator: i64 },
+    String(String),
+    Character(char),
+    Symbol(Arc<str>),
+    // `auto_resolved` keywords are written `::name` and belong to a namespace
+    // only the reader of the document knows
+    Keyword { namespace: Option<Arc<str>>, name: Arc<str>, auto_resolved: bool },
+    List(Vec<Value>),
+    Vector(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    // a map read with `MapBacking::Indexed`, equal to the `Value::Map` with
+    // the same entries
+    #[cfg(feature = "indexmap")]
+    IndexedMap(IndexMap<Value, Value>),
+    Set(Vec<Value>),
+    Instant(Instant),
+    Uuid(Uuid),
+    Tagged { tag: String, value: Box<Value> },
+}
+
+impl Value {
+    // whitespace and reader macros carry no value of their own; names are
+    // shared through `interner` when there is one
+    pub(crate) fn from_token(token: Token, interner: Option<&Interner>) -> Option<Value> {
+        let intern = |name: &str| match interner {
+            Some(interner) => interner.intern(name),
+            None => Arc::from(name),
+        };
+
+        match token {
+            Token::Nil => Some(Value::Nil),
+            Token::Boolean(b) => Some(Value::Bool(b)),
+            Token::Integer(i) => Some(Value::Integer(i)),
+            Token::BigInteger(i) => Some(Value::BigInteger(i)),
+            Token::Float(f) => Some(Value::Float(f)),
+            Token::BigDecimal(d) => Some(Value::BigDecimal(d)),
+            Token::Ratio { numerator, denominator } => Some(Value::Ratio { numerator, denominator }),
+            Token::String(s) => Some(Value::String(s)),
+            Token::Character(c) => Some(Value::Character(c)),
+            Token::Symbol { namespace: Some(namespace), name } => Some(Value::Symbol(intern(&format!("{}/{}", namespace, name)))),
+            Token::Symbol { namespace: None, name } => Some(Value::Symbol(intern(name))),
+            Token::Keyword { namespace, name, auto_resolved } => {
+                Some(Value::Keyword { namespace: namespace.map(|ns| intern(&ns)), name: intern(&name), auto_resolved })
+            },
+            Token::Open(_) | Token::OpenSet | Token::Close(_) | Token::Whitespace(_) | Token::Comma | Token::Comment(_) | Token::Discard | Token::Tag(_) | Token::NamespacedMap(_) => None,
+        }
+    }
+
+    /// A short, stable name for the kind of value, for use in messages such
+    /// as "expected integer, found string".
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Value::Nil => "nil",
+            Value::Bool(_) => "boolean",
+            Value::Integer(_) => "integer",
+            Value::BigInteger(_) => "big integer",
+            Value::Float(_) => "float",
+            Value::BigDecimal(_) => "big decimal",
+            Value::Ratio { .. } => "ratio",
+            Value::String(_) => "string",
+            Value::Character(_) => "character",
+            Value::Symbol(_) => "symbol",
+            Value::Keyword { .. } => "keyword",
+            Value::List(_) => "list",
+            Value::Vector(_) => "vector",
+            Value::Map(_) => "map",
+            #[cfg(feature = "indexmap")]
+            Value::IndexedMap(_) => "map",
+            Value::Set(_) => "set",
+            Value::Instant(_) => "instant",
+            Value::Uuid(_) => "uuid",
+            Value::Tagged { .. } => "tagged",
+        }
+    }
+
+    pub fn list<I>(items: I) -> Value where I: IntoIterator<Item = Value> {
+        Value::List(items.into_iter().collect())
+    }
+
+    pub fn vector<I>(items: I) -> Value where I: IntoIterator<Item = Value> {
+        Value::Vector(items.into_iter().collect())
+    }
+
+    /// Items are kept as given; duplicates are not removed.
+    pub fn set<I>(items: I) -> Value where I: IntoIterator<Item = Value> {
+        Value::Set(items.into_iter().collect())
+    }
+
+    /// Entries are kept in the order given; duplicate keys are not removed.
+    pub fn map<I>(pairs: I) -> Value where I: IntoIterator<Item = (Value, Value)> {
+        Value::Map(pairs.into_iter().collect())
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Integer(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Only floats give a value; integers are a distinct type in EDN.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_symbol(&self) -> Option<&str> {
+        match *self {
+            Value::Symbol(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match *self {
+            Value::List(ref items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_vector(&self) -> Option<&[Value]> {
+        match *self {
+            Value::Vector(ref items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&[(Value, Value)]> {
+        match *self {
+            Value::Map(ref pairs) => Some(pairs),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "indexmap")]
+    pub fn as_indexed_map(&self) -> Option<&IndexMap<Value, Value>> {
+        match *self {
+            Value::IndexedMap(ref map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// The entries of a map of either kind, as key and value pairs in the
+    /// order they were written; any other value has none.
+    pub fn entries(&self) -> Entries<'_> {
+        Entries {
+            pairs: self.as_map().unwrap_or(&[]).iter(),
+            #[cfg(feature = "indexmap")]
+            indexed: self.as_indexed_map().map(IndexMap::iter),
+        }
+    }
+
+    pub fn as_set(&self) -> Option<&[Value]> {
+        match *self {
+            Value::Set(ref items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn is_nil(&self) -> bool {
+        matches!(*self, Value::Nil)
+    }
+
+    /// Lists, vectors, maps and sets.
+    pub fn is_collection(&self) -> bool {
+        match *self {
+            Value::List(_) | Value::Vector(_) | Value::Map(_) | Value::Set(_) => true,
+            #[cfg(feature = "indexmap")]
+            Value::IndexedMap(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Lists and vectors, the collections whose items keep their order.
+    pub fn is_seq(&self) -> bool {
+        matches!(*self, Value::List(_) | Value::Vector(_))
+    }
+
+    /// Everything other than a collection or tagged value, as for
+    /// `Visitor::visit_scalar`; `#inst` and `#uuid` values are scalars.
+    pub fn is_scalar(&self) -> bool {
+        !self.is_collection() && !matches!(*self, Value::Tagged { .. })
+    }
+
+    /// Whether a collection has no items or a string no characters. No other
+    /// value is empty.
+    pub fn is_empty(&self) -> bool {
+        match *self {
+            Value::String(ref s) => s.is_empty(),
+            Value::List(ref items) | Value::Vector(ref items) | Value::Set(ref items) => items.is_empty(),
+            Value::Map(ref pairs) => pairs.is_empty(),
+            #[cfg(feature = "indexmap")]
+            Value::IndexedMap(ref map) => map.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// The value `key` maps to, or `None` for a missing key or a non-map.
+    /// A `Value::Map` is searched entry by entry, while a `Value::IndexedMap`
+    /// finds the key by its hash.
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        match *self {
+            #[cfg(feature = "indexmap")]
+            Value::IndexedMap(ref map) => map.get(key),
+            _ => self.as_map()?.iter().find(|&(k, _)| k == key).map(|(_, v)| v),
+        }
+    }
+
+    /// The value reached by following `path` down from this one, or `None`
+    /// where a key is missing, an index is out of range, or a step does not
+    /// fit the value it is taken from. An empty path gives this value.
+    pub fn get_path(&self, path: &[PathSegment]) -> Option<&Value> {
+        path.iter().try_fold(self, |value, segment| match *segment {
+            PathSegment::Key(ref key) => value.get(key),
+            PathSegment::Index(index) => value.as_vector().or_else(|| value.as_list())?.get(index),
+        })
+    }
+
+    /// Like `get_path`, with the path written as for `PathSegment::parse`,
+    /// such as `"servers/0/host"`.
+    pub fn get_path_str(&self, path: &str) -> Option<&Value> {
+        self.get_path(&PathSegment::parse(path))
+    }
+
+    /// Rebuilds the tree with `f` applied to every value in it, children
+    /// before the collection holding them. Collections keep their kind, and
+    /// the result of `f` on the whole tree is returned.
+    pub fn map_values<F>(self, mut f: F) -> Value where F: FnMut(Value) -> Value {
+        self.map_with(&mut f)
+    }
+
+    fn map_with<F>(self, f: &mut F) -> Value where F: FnMut(Value) -> Value {
+        let value = match self {
+            Value::List(items) => Value::List(items.into_iter().map(|item| item.map_with(f)).collect()),
+            Value::Vector(items) => Value::Vector(items.into_iter().map(|item| item.map_with(f)).collect()),
+            Value::Set(items) => Value::Set(items.into_iter().map(|item| item.map_with(f)).collect()),
+            Value::Map(pairs) => Value::Map(pairs.into_iter().map(|(k, v)| (k.map_with(f), v.map_with(f))).collect()),
+            #[cfg(feature = "indexmap")]
+            Value::IndexedMap(map) => Value::IndexedMap(map.into_iter().map(|(k, v)| (k.map_with(f), v.map_with(f))).collect()),
+            Value::Tagged { tag, value } => Value::Tagged { tag, value: Box::new(value.map_with(f)) },
+            value => value,
+        };
+
+        f(value)
+    }
+
+    /// Merges the map `other` into the map `self`, as when applying overrides
+    /// to a base configuration. Where both give a key a map the two are merged
+    /// in turn; otherwise the value from `other` replaces the one in `self`.
+    /// New keys follow those of `self`, in the order `other` gives them, and
+    /// the result is held as `self` is.
+    ///
+    /// Fails with a message such as "expected map, found vector" when either
+    /// side is not a map.
+    pub fn merge(self, other: Value) -> Result<Value, String> {
+        match (self, other) {
+            #[cfg(feature = "indexmap")]
+            (Value::IndexedMap(map), other) => match Value::Map(map.into_iter().collect()).merge(other)? {
+                Value::Map(pairs) => Ok(Value::IndexedMap(pairs.into_iter().collect())),
+                _ => unreachable!("maps merge into a Value::Map"),
+            },
+            #[cfg(feature = "indexmap")]
+            (value @ Value::Map(_), Value::IndexedMap(others)) => value.merge(Value::Map(others.into_iter().collect())),
+            (Value::Map(mut pairs), Value::Map(others)) => {
+                for (key, value) in others {
+                    match pairs.iter().position(|(k, _)| *k == key) {
+                        Some(index) => {
+                            let existing = mem::replace(&mut pairs[index].1, Value::Nil);
+
+                            pairs[index].1 = match (existing, value) {
+                                (existing, value) if is_map(&existing) && is_map(&value) => {
+                                    existing.merge(value)?
+                                },
+                                (_, value) => value,
+                            };
+                        },
+                        None => pairs.push((key, value)),
+                    }
+                }
+
+                Ok(Value::Map(pairs))
+            },
+            (Value::Map(_), other) => Err(format!("expected map, found {}", other.type_name())),
+            (value, _) => Err(format!("expected map, found {}", value.type_name())),
+        }
+    }
+}
+
+// a map held either way
+fn is_map(value: &Value) -> bool {
+    match *value {
+        Value::Map(_) => true,
+        #[cfg(feature = "indexmap")]
+        Value::IndexedMap(_) => true,
+        _ => false,
+    }
+}
+
+// `a` and `b` hold the same items, each as many times, in any order; as `Ord`
+// agrees with `PartialEq`, they do when sorting them gives the same items
+fn same_items<T: Ord>(a: &[T], b: &[T]) -> bool {
+    (a.len() == b.len()) && (sorted(a) == sorted(b))
+}
+
+// the bits that decide whether floats are equal: `-0.0` is `0.0`, and every
+// `NaN` is the same `NaN`, so that `Eq` holds for floats
+fn float_bits(f: f64) -> u64 {
+    if f == 0.0 {
+        0
+    } else if f.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        f.to_bits()
+    }
+}
+
+// FNV-1a, for hashing items on their own without needing `std`
+struct ItemHasher(u64);
+
+impl Hasher for ItemHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0 ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// `a` and `b` hold the same entries, in any order
+#[cfg(feature = "indexmap")]
+fn same_entries(a: &[(Value, Value)], b: &IndexMap<Value, Value>) -> bool {
+    (a.len() == b.len()) && a.iter().all(|(k, v)| b.get(k) == Some(v))
+}
+
+// the hash of `item` on its own
+pub(crate) fn item_hash<T: Hash>(item: &T) -> u64 {
+    let mut hasher = ItemHasher(0xcbf2_9ce4_8422_2325);
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+// combines the items' hashes so that their order does not matter
+fn unordered_hash<I>(items: I) -> u64 where I: IntoIterator, I::Item: Hash {
+    items.into_iter().fold(0, |acc: u64, item| acc.wrapping_add(item_hash(&item)))
+}
+
+/// Equality as EDN defines it: sets and maps ignore the order their items
+/// were written in, while integers and floats, or lists and vectors, never
+/// equal each other. Unlike `f64`, floats equal themselves even when `NaN`,
+/// so that `Eq` holds; `-0.0` still equals `0.0`.
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::BigInteger(a), Value::BigInteger(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => float_bits(*a) == float_bits(*b),
+            (Value::BigDecimal(a), Value::BigDecimal(b)) => a == b,
+            (Value::Ratio { numerator: a_numerator, denominator: a_denominator },
+             Value::Ratio { numerator: b_numerator, denominator: b_denominator }) => {
+                (a_numerator == b_numerator) && (a_denominator == b_denominator)
+            },
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Character(a), Value::Character(b)) => a == b,
+            (Value::Symbol(a), Value::Symbol(b)) => a == b,
+            (Value::Keyword { namespace: a_namespace, name: a_name, auto_resolved: a_auto },
+             Value::Keyword { namespace: b_namespace, name: b_name, auto_resolved: b_auto }) => {
+                (a_namespace == b_namespace) && (a_name == b_name) && (a_auto == b_auto)
+            },
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Vector(a), Value::Vector(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => same_items(a, b),
+            (Value::Set(a), Value::Set(b)) => same_items(a, b),
+            #[cfg(feature = "indexmap")]
+            (Value::IndexedMap(a), Value::IndexedMap(b)) => a == b,
+            #[cfg(feature = "indexmap")]
+            (Value::Map(a), Value::IndexedMap(b)) | (Value::IndexedMap(b), Value::Map(a)) => same_entries(a, b),
+            (Value::Instant(a), Value::Instant(b)) => a == b,
+            (Value::Uuid(a), Value::Uuid(b)) => a == b,
+            (Value::Tagged { tag: a_tag, value: a_value },
+             Value::Tagged { tag: b_tag, value: b_value }) => (a_tag == b_tag) && (a_value == b_value),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+/// Consistent with `PartialEq`: reordering a set or map leaves its hash alone.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            // as the `Value::Map` it equals
+            #[cfg(feature = "indexmap")]
+            Value::IndexedMap(_) => mem::discriminant(&Value::Map(vec!())).hash(state),
+            _ => mem::discriminant(self).hash(state),
+        }
+
+        match self {
+            Value::Nil => {},
+            Value::Bool(b) => b.hash(state),
+            Value::Integer(i) => i.hash(state),
+            Value::BigInteger(s) | Value::BigDecimal(s) | Value::String(s) => s.hash(state),
+            Value::Symbol(s) => s.hash(state),
+            Value::Float(f) => float_bits(*f).hash(state),
+            Value::Ratio { numerator, denominator } => {
+                numerator.hash(state);
+                denominator.hash(state);
+            },
+            Value::Character(c) => c.hash(state),
+            Value::Keyword { namespace, name, auto_resolved } => {
+                namespace.hash(state);
+                name.hash(state);
+                auto_resolved.hash(state);
+            },
+            Value::List(items) | Value::Vector(items) => items.hash(state),
+            Value::Map(pairs) => {
+                pairs.len().hash(state);
+                unordered_hash(pairs).hash(state);
+            },
+            #[cfg(feature = "indexmap")]
+            Value::IndexedMap(map) => {
+                map.len().hash(state);
+                unordered_hash(map).hash(state);
+            },
+            Value::Set(items) => {
+                items.len().hash(state);
+                unordered_hash(items).hash(state);
+            },
+            Value::Instant(instant) => instant.hash(state),
+            Value::Uuid(uuid) => uuid.hash(state),
+            Value::Tagged { tag, value } => {
+                tag.hash(state);
+                value.hash(state);
+            },
+        }
+    }
+}
+
+/// A total order, consistent with `PartialEq`. Values go by kind first, in the order nil,
+/// booleans, numbers, characters, strings, keywords, symbols, instants, UUIDs,
+/// lists, vectors, sets, maps and tagged values, and then:
+///
+/// * `false` before `true`
+/// * numbers of every kind by their value, as near as `f64` can tell, with
+///   `##NaN` last; numbers of equal value go integers, ratios, big integers,
+///   floats then big decimals
+/// * characters, strings and symbols by code point
+/// * keywords by namespace, those without one first, and then by name, with
+///   `::name` after `:name`
+/// * instants by the moment they name, then by their UTC offset
+/// * UUIDs by their bytes
+/// * lists and vectors item by item, a shorter one first when it is a prefix
+///   of the other
+/// * sets and maps likewise once sorted, a map comparing keys before values
+/// * tagged values by tag, then by value
+impl Ord for Value {
+    fn cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Character(a), Value::Character(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Symbol(a), Value::Symbol(b)) => a.cmp(b),
+            (Value::Keyword { namespace: a_namespace, name: a_name, auto_resolved: a_auto },
+             Value::Keyword { namespace: b_namespace, name: b_name, auto_resolved: b_auto }) => {
+                a_namespace.cmp(b_namespace).then_with(|| a_name.cmp(b_name)).then_with(|| a_auto.cmp(b_auto))
+            },
+            (Value::Instant(a), Value::Instant(b)) => instant_key(a).cmp(&instant_key(b)),
+            (Value::Uuid(a), Value::Uuid(b)) => a.as_bytes().cmp(b.as_bytes()),
+            (Value::List(a), Value::List(b)) | (Value::Vector(a), Value::Vector(b)) => a.cmp(b),
+            (Value::Set(a), Value::Set(b)) => sorted(a).cmp(&sorted(b)),
+            (Value::Tagged { tag: a_tag, value: a_value }, Value::Tagged { tag: b_tag, value: b_value }) => {
+                a_tag.cmp(b_tag).then_with(|| a_value.cmp(b_value))
+            },
+            (a, b) if (rank(a) == 2) && (rank(b) == 2) => compare_numbers(a, b),
+            (a, b) if (rank(a) == 12) && (rank(b) == 12) => sorted_entries(a).cmp(&sorted_entries(b)),
+            (a, b) => rank(a).cmp(&rank(b)),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// the place of each kind of value in the order, numbers sharing theirs
+fn rank(value: &Value) -> u8 {
+    match *value {
+        Value::Nil => 0,
+        Value::Bool(_) => 1,
+        Value::Integer(_) | Value::Ratio { .. } | Value::BigInteger(_) | Value::Float(_) | Value::BigDecimal(_) => 2,
+        Value::Character(_) => 3,
+        Value::String(_) => 4,
+        Value::Keyword { .. } => 5,
+        Value::Symbol(_) => 6,
+        Value::Instant(_) => 7,
+        Value::Uuid(_) => 8,
+        Value::List(_) => 9,
+        Value::Vector(_) => 10,
+        Value::Set(_) => 11,
+        Value::Map(_) => 12,
+        #[cfg(feature = "indexmap")]
+        Value::IndexedMap(_) => 12,
+        Value::Tagged { .. } => 13,
+    }
+}
+
+fn sorted<T: Ord>(items: &[T]) -> Vec<&T> {
+    let mut items: Vec<&T> = items.iter().collect();
+    items.sort();
+    items
+}
+
+// the entries of a map of either kind
+fn sorted_entries(map: &Value) -> Vec<(&Value, &Value)> {
+    let mut entries: Vec<(&Value, &Value)> = match *map {
+        Value::Map(ref pairs) => pairs.iter().map(|(k, v)| (k, v)).collect(),
+        #[cfg(feature = "indexmap")]
+        Value::IndexedMap(ref map) => map.iter().collect(),
+        _ => vec!(),
+    };
+
+    entries.sort();
+    entries
+}
+
+// by value, then by kind, then exactly within a kind
+fn compare_numbers(a: &Value, b: &Value) -> Ordering {
+    let kind = |value: &Value| match *value {
+        Value::Integer(_) => 0,
+        Value::Ratio { .. } => 1,
+        Value::BigInteger(_) => 2,
+        Value::Float(_) => 3,
+        _ => 4,
+    };
+
+    let exact = || match (a, b) {
+        (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+        (&Value::Ratio { numerator: a_numerator, denominator: a_denominator },
+         &Value::Ratio { numerator: b_numerator, denominator: b_denominator }) => {
+            let (a_scaled, b_scaled) = (i128::from(a_numerator) * i128::from(b_denominator),
+                                        i128::from(b_numerator) * i128::from(a_denominator));
+            // ratios not in lowest terms can be equal without being `==`
+            a_scaled.cmp(&b_scaled).then_with(|| a_numerator.cmp(&b_numerator))
+        },
+        (Value::BigInteger(a), Value::BigInteger(b)) => compare_integer_text(a, b),
+        (Value::BigDecimal(a), Value::BigDecimal(b)) => compare_decimal_text(a, b),
+        _ => Ordering::Equal,
+    };
+
+    compare_f64(approximate(a), approximate(b)).then_with(|| kind(a).cmp(&kind(b))).then_with(exact)
+}
+
+fn approximate(number: &Value) -> f64 {
+    match *number {
+        Value::Integer(i) => i as f64,
+        Value::Ratio { numerator, denominator } => numerator as f64 / denominator as f64,
+        Value::Float(f) => f,
+        Value::BigInteger(ref s) | Value::BigDecimal(ref s) => s.parse().unwrap_or(f64::NAN),
+        _ => f64::NAN,
+    }
+}
+
+// `-0.0` equals `0.0`, and `NaN` comes after everything else
+fn compare_f64(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+}
+
+// the digits of big integers, which may have a leading `-`
+fn compare_integer_text(a: &str, b: &str) -> Ordering {
+    let magnitude = |s: &'_ str| {
+        let digits = s.trim_start_matches('-').trim_start_matches('0');
+        (digits.len(), String::from(digits))
+    };
+
+    let by_value = match (a.starts_with('-'), b.starts_with('-')) {
+        (false, true) => Ordering::Greater,
+        (true, false) => Ordering::Less,
+        (false, false) => magnitude(a).cmp(&magnitude(b)),
+        (true, true) => magnitude(b).cmp(&magnitude(a)),
+    };
+
+    by_value.then_with(|| a.cmp(b))
+}
+
+// the text of big decimals, which may have a sign, a fraction and an exponent
+fn compare_decimal_text(a: &str, b: &str) -> Ordering {
+    // the sign of the value, then its significant digits and exponent as
+    // `0.DIGITS * 10^EXPONENT`, so that a larger exponent is a larger magnitude
+    let parts = |s: &str| {
+        let negative = s.starts_with('-');
+        let s = s.trim_start_matches(['-', '+']);
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(i) => (&s[..i], s[i + 1..].parse().unwrap_or(0)),
+            None => (s, 0),
+        };
+        let (whole, fraction) = mantissa.split_at(mantissa.find('.').unwrap_or(mantissa.len()));
+        let digits = format!("{}{}", whole, fraction.trim_start_matches('.'));
+        let significant = digits.trim_start_matches('0');
+        let exponent = exponent + (whole.len() as i64) - ((digits.len() - significant.len()) as i64);
+        let significant = String::from(significant.trim_end_matches('0'));
+
+        match (significant.is_empty(), negative) {
+            (true, _) => (0, 0, significant),
+            (false, negative) => (if negative { -1 } else { 1 }, exponent, significant),
+        }
+    };
+
+    let ((a_sign, a_exponent, a_digits), (b_sign, b_exponent, b_digits)) = (parts(a), parts(b));
+    let magnitude = (a_exponent, a_digits).cmp(&(b_exponent, b_digits));
+    let by_value = a_sign.cmp(&b_sign).then(if a_sign < 0 { magnitude.reverse() } else { magnitude });
+
+    by_value.then_with(|| a.cmp(b))
+}
+
+// the moment an instant names, in seconds and nanoseconds since 1970 in UTC,
+// then its offset
+fn instant_key(instant: &Instant) -> (i64, u32, i16) {
+    // days since 1970-01-01 of a date in the proleptic Gregorian calendar
+    let (year, month, day) = (i64::from(instant.year()), i64::from(instant.month()), i64::from(instant.day()));
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = days * 86_400 + i64::from(instant.hour()) * 3600 + i64::from(instant.minute()) * 60 + i64::from(instant.second())
+        - i64::from(instant.offset_minutes()) * 60;
+
+    (seconds, instant.nanosecond(), instant.offset_minutes())
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\r' => write!(f, "\\r")?,
+            '\u{c}' => write!(f, "\\f")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+
+    write!(f, "\"")
+}
+
+fn write_items(f: &mut fmt::Formatter, open: &str, items: &[Value], close: &str) -> fmt::Result {
+    write!(f, "{}", open)?;
+
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+
+        write!(f, "{}", item)?;
+    }
+
+    write!(f, "{}", close)
+}
+
+fn write_entries<'a, I>(f: &mut fmt::Formatter, entries: I) -> fmt::Result where I: Iterator<Item = (&'a Value, &'a Value)> {
+    write!(f, "{{")?;
+
+    for (i, (key, value)) in entries.enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+
+        write!(f, "{} {}", key, value)?;
+    }
+
+    write!(f, "}}")
+}
+
+/// Renders the value as EDN text that reads back as an equal value.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::BigInteger(ref i) => write!(f, "{}N", i),
+            Value::Float(n) if n.is_nan() => write!(f, "##NaN"),
+            Value::Float(n) if n.is_infinite() => write!(f, "{}", if n > 0.0 { "##Inf" } else { "##-Inf" }),
+            // `{:?}` keeps the `.0` that marks `1.0` as a float
+            Value::Float(n) => write!(f, "{:?}", n),
+            Value::BigDecimal(ref d) => write!(f, "{}M", d),
+            Value::Ratio { numerator, denominator } => write!(f, "{}/{}", numerator, denominator),
+            Value::String(ref s) => write_string(f, s),
+            Value::Character(c) => match CHARACTER_NAMES.iter().find(|&&(_, named)| named == c) {
+                Some(&(name, _)) => write!(f, "\\{}", name),
+                None => write!(f, "\\{}", c),
+            },
+            Value::Symbol(ref s) => write!(f, "{}", s),
+            Value::Keyword { ref namespace, ref name, auto_resolved } => {
+                write!(f, "{}", if auto_resolved { "::" } else { ":" })?;
+
+                match *namespace {
+                    Some(ref namespace) => write!(f, "{}/{}", namespace, name),
+                    None => write!(f, "{}", name),
+                }
+            },
+            Value::List(ref items) => write_items(f, "(", items, ")"),
+            Value::Vector(ref items) => write_items(f, "[", items, "]"),
+            Value::Map(ref pairs) => write_entries(f, pairs.iter().map(|(k, v)| (k, v))),
+            #[cfg(feature = "indexmap")]
+            Value::IndexedMap(ref map) => write_entries(f, map.iter()),
+            Value::Set(ref items) => write_items(f, "#{", items, "}"),
+            Value::Instant(ref instant) => write!(f, "#inst \"{}\"", instant),
+            Value::Uuid(ref uuid) => write!(f, "#uuid \"{}\"", uuid),
+            Value::Tagged { ref tag, ref value } => write!(f, "#{} {}", tag, value),
+        }
+    }
+}
+
+/// Indexes into a vector or list, panicking like `Vec` when `index` is out of
+/// range, and on any other kind of value.
+impl Index<usize> for Value {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        match *self {
+            Value::Vector(ref items) | Value::List(ref items) => &items[index],
+            _ => panic!("cannot index into {}", self),
+        }
+    }
+}
+
+/// Iterates the items of a list, vector or set, or the entries of a map as
+/// two-item `[key value]` vectors, as Clojure gives map entries, in the order
+/// they were written. Any other value has no items, rather than panicking.
+impl IntoIterator for Value {
+    type Item = Value;
+    type IntoIter = vec::IntoIter<Value>;
+
+    fn into_iter(self) -> vec::IntoIter<Value> {
+        match self {
+            Value::List(items) | Value::Vector(items) | Value::Set(items) => items.into_iter(),
+            Value::Map(pairs) => pairs.into_iter().map(|(k, v)| Value::Vector(vec!(k, v))).collect::<Vec<Value>>().into_iter(),
+            #[cfg(feature = "indexmap")]
+            Value::IndexedMap(map) => map.into_iter().map(|(k, v)| Value::Vector(vec!(k, v))).collect::<Vec<Value>>().into_iter(),
+            _ => vec!().into_iter(),
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Value {
+        Value::Integer(i)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Value {
+        Value::Float(f)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value {
+        Value::Bool(b)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Value {
+        Value::String(s)
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(s: &'a str) -> Value {
+        Value::String(String::from(s))
+    }
+}
+
+/// Converts to a vector.
+impl From<Vec<Value>> for Value {
+    fn from(items: Vec<Value>) -> Value {
+        Value::Vector(items)
+    }
+}
+
+/// Collects into a vector.
+impl FromIterator<Value> for Value {
+    fn from_iter<I>(items: I) -> Value where I: IntoIterator<Item = Value> {
+        Value::vector(items)
+    }
+}
+
+/// One step of a path into a value, as followed by `Value::get_path`.
+#[derive(Debug,Clone,PartialEq)]
+pub enum PathSegment {
+    /// The value a map gives this key.
+    Key(Value),
+    /// The item at this position of a vector or list.
+    Index(usize),
+}
+
+impl PathSegment {
+    /// Splits `path` at each `/`. A segment of digits is an index and any
+    /// other the keyword of that name, so `"servers/0/host"` reads as
+    /// `[:servers 0 :host]`; other keys, such as strings or keywords with a
+    /// namespace, need a `PathSegment::Key`. The empty path has no segments.
+    pub fn parse(path: &str) -> Vec<PathSegment> {
+        if path.is_empty() {
+            return vec!();
+        }
+
+        path.split('/').map(|segment| match segment.parse() {
+            Ok(index) if segment.bytes().all(|b| b.is_ascii_digit()) => PathSegment::Index(index),
+            _ => PathSegment::Key(Value::Keyword { namespace: None, name: Arc::from(segment), auto_resolved: false }),
+        }).collect()
+    }
+}
+
+/// Borrowed items of a list, vector or set, as given by the `IntoIterator`
+/// of `&Value`. A map has no items of its own to borrow as entries, so has
+/// none here; `Value::entries` gives them as pairs.
+pub struct Items<'a> {
+    items: slice::Iter<'a, Value>,
+}
+
+impl<'a> Iterator for Items<'a> {
+    type Item = &'a Value;
+
+    fn next(&mut self) -> Option<&'a Value> {
+        self.items.next()
+    }
+}
+
+impl<'a> IntoIterator for &'a Value {
+    type Item = &'a Value;
+    type IntoIter = Items<'a>;
+
+    fn into_iter(self) -> Items<'a> {
+        let items: &[Value] = match *self {
+            Value::List(ref items) | Value::Vector(ref items) | Value::Set(ref items) => items,
+            _ => &[],
+        };
+
+        Items { items: items.iter() }
+    }
+}
+
+/// The entries of a map, as given by `Value::entries`.
+pub struct Entries<'a> {
+    pairs: slice::Iter<'a, (Value, Value)>,
+    #[cfg(feature = "indexmap")]
+    indexed: Option<index_map::Iter<'a, Value, Value>>,
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = (&'a Value, &'a Value);
+
+    fn next(&mut self) -> Option<(&'a Value, &'a Value)> {
+        let entry = self.pairs.next().map(|(k, v)| (k, v));
+        #[cfg(feature = "indexmap")]
+        let entry = entry.or_else(|| self.indexed.as_mut()?.next());
+        entry
+    }
+}
+
+impl FromStr for Value {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Value, ParseError> {
+        parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyword(name: &str) -> Value {
+        Value::Keyword { namespace: None, name: Arc::from(name), auto_resolved: false }
+    }
+
+    #[test]
+    fn nested_value_test() {
+        let value = Value::Map(vec![
+            (keyword("items"), Value::Vector(vec![Value::Integer(1), Value::List(vec![Value::Nil])])),
+            (keyword("tags"), Value::Set(vec![Value::Symbol("a".into())])),
+        ]);
+
+        assert_eq!(value.clone(), value);
+
+        let different = Value::Map(vec![
+            (keyword("items"), Value::Vector(vec![Value::Integer(1), Value::Vector(vec![Value::Nil])])),
+            (keyword("tags"), Value::Set(vec![Value::Symbol("a".into())])),
+        ]);
+        assert!(value != different);
+
+        if let Value::Map(ref pairs) = value {
+            assert_eq!(keyword("items"), pairs[0].0);
+            assert_eq!(Value::Vector(vec![Value::Integer(1), Value::List(vec![Value::Nil])]), pairs[0].1);
+        } else {
+            panic!("expected a map");
+        }
+
+        assert!(Value::List(vec!()) != Value::Vector(vec!()));
+    }
+
+    #[test]
+    fn equality_test() {
+        assert_eq!(parse("#{1 2 [3]}").unwrap(), parse("#{[3] 2 1}").unwrap());
+        assert_eq!(parse("{:a 1 :b {:c #{2 3}}}").unwrap(), parse("{:b {:c #{3 2}} :a 1}").unwrap());
+        assert_eq!(parse("[#{:x :y}]").unwrap(), parse("[#{:y :x}]").unwrap());
+
+        assert!(parse("[1 2]").unwrap() != parse("(1 2)").unwrap());
+        assert!(parse("[1 2]").unwrap() != parse("[2 1]").unwrap());
+        assert!(parse("{:a 1 :b 2}").unwrap() != parse("{:a 2 :b 1}").unwrap());
+        assert!(parse("#{1 2}").unwrap() != parse("#{1 2 3}").unwrap());
+        assert!(Value::Integer(1) != Value::Float(1.0));
+        assert_eq!(Value::Float(-0.0), Value::Float(0.0));
+        assert_eq!(Value::Float(f64::NAN), Value::Float(-f64::NAN));
+
+        let error = parse("#{#{1 2} #{2 1}}").unwrap_err();
+        assert_eq!("duplicate set element #{2 1}", error.message());
+        assert_eq!("duplicate set element ##NaN", parse("#{##NaN ##NaN}").unwrap_err().message());
+        assert_eq!("duplicate map key ##NaN", parse("{##NaN 1 ##NaN 2}").unwrap_err().message());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hash_test() {
+        use std::collections::HashSet;
+
+        let mut set = HashSet::new();
+        set.insert(parse("#{1 2 3}").unwrap());
+        set.insert(parse("#{3 1 2}").unwrap());
+        set.insert(parse("#{2 3 1}").unwrap());
+        assert_eq!(1, set.len());
+
+        set.insert(parse("{#{:a :b} [1 2]}").unwrap());
+        set.insert(parse("{#{:b :a} [1 2]}").unwrap());
+        assert_eq!(2, set.len());
+
+        set.insert(parse("[1 2 3]").unwrap());
+        set.insert(parse("(1 2 3)").unwrap());
+        assert_eq!(4, set.len());
+
+        assert!(set.contains(&parse("#{3 2 1}").unwrap()));
+
+        set.insert(parse("##NaN").unwrap());
+        set.insert(Value::Float(-0.0));
+        assert!(set.contains(&Value::Float(f64::NAN)));
+        assert!(set.contains(&Value::Float(0.0)));
+        assert!(!set.contains(&parse("#{1 2}").unwrap()));
+
+        assert!(Value::Set(vec![Value::Nil, Value::Nil, keyword("a")]) != Value::Set(vec![Value::Nil, keyword("a"), keyword("a")]));
+    }
+
+    #[test]
+    fn from_token_test() {
+        assert_eq!(Some(Value::Nil), Value::from_token(Token::Nil, None));
+        assert_eq!(Some(Value::Symbol("ab".into())), Value::from_token(Token::Symbol { namespace: None, name: "ab" }, None));
+        assert_eq!(None, Value::from_token(Token::Whitespace(" "), None));
+    }
+
+    #[test]
+    fn accessor_test() {
+        assert_eq!(Some(true), Value::Bool(true).as_bool());
+        assert_eq!(Some(-3), Value::Integer(-3).as_i64());
+        assert_eq!(Some(1.5), Value::Float(1.5).as_f64());
+        assert_eq!(Some("s"), Value::String(String::from("s")).as_str());
+        assert_eq!(Some("sym"), Value::Symbol("sym".into()).as_symbol());
+        assert_eq!(Some(&[Value::Nil][..]), Value::List(vec![Value::Nil]).as_list());
+        assert_eq!(Some(&[Value::Nil][..]), Value::Vector(vec![Value::Nil]).as_vector());
+        assert_eq!(Some(&[(keyword("a"), Value::Nil)][..]), Value::Map(vec![(keyword("a"), Value::Nil)]).as_map());
+        assert_eq!(Some(&[Value::Nil][..]), Value::Set(vec![Value::Nil]).as_set());
+
+        let value = Value::Integer(1);
+        assert_eq!(None, value.as_bool());
+        assert_eq!(None, value.as_f64());
+        assert_eq!(None, value.as_str());
+        assert_eq!(None, value.as_list());
+        assert_eq!(None, value.as_map());
+        assert_eq!(None, Value::Float(1.0).as_i64());
+        assert_eq!(None, Value::String(String::from("s")).as_symbol());
+        assert_eq!(None, Value::List(vec![]).as_vector());
+        assert_eq!(None, Value::Vector(vec![]).as_set());
+    }
+
+    #[test]
+    fn predicate_test() {
+        let value = |s| parse(s).unwrap();
+
+        for s in &["()", "[1]", "{}", "#{:a}"] {
+            assert!(value(s).is_collection() && !value(s).is_scalar(), "{}", s);
+        }
+
+        for s in &["nil", "1", "\"s\"", ":k", "sym", "\\c", "#inst \"1985-04-12T23:20:50Z\""] {
+            assert!(value(s).is_scalar() && !value(s).is_collection() && !value(s).is_seq(), "{}", s);
+        }
+
+        assert!(value("(1)").is_seq() && value("[]").is_seq());
+        assert!(!value("{}").is_seq() && !value("#{}").is_seq());
+
+        let tagged = value("#point [1 2]");
+        assert!(!tagged.is_scalar() && !tagged.is_collection());
+
+        assert!(Value::Nil.is_nil());
+        assert!(!value("[]").is_nil() && !value("false").is_nil());
+
+        for s in &["[]", "()", "{}", "#{}", "\"\""] {
+            assert!(value(s).is_empty(), "{}", s);
+        }
+
+        for s in &["[nil]", "{:a 1}", "#{1}", "\" \"", "nil", "0", "#tag []"] {
+            assert!(!value(s).is_empty(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn type_name_test() {
+        let names = ["nil", "boolean", "integer", "big integer", "float", "big decimal", "string", "character",
+                     "symbol", "keyword", "list", "vector", "map", "set", "instant", "uuid", "tagged"];
+        let source = "[nil true 1 1N 1.5 1.5M \"s\" \\c sym :kw () [] {} #{} \
+                      #inst \"2020-01-01T00:00:00Z\" #uuid \"f81d4fae-7dec-11d0-a765-00a0c91e6bf6\" #point [1 2]]";
+
+        let values = parse(source).unwrap();
+        let found: Vec<&str> = values.as_vector().unwrap().iter().map(Value::type_name).collect();
+        assert_eq!(&names[..], &found[..]);
+    }
+
+    #[test]
+    fn map_values_test() {
+        let value = parse("{:a [1 (2 3)] :b #{4} 5 #tag 6}").unwrap();
+        let doubled = value.map_values(|v| match v {
+            Value::Integer(i) => Value::Integer(i * 2),
+            v => v,
+        });
+        assert_eq!(parse("{:a [2 (4 6)] :b #{8} 10 #tag 12}").unwrap(), doubled);
+
+        let value = parse("[\"ab\" (\"c\" {\"k\" \"v\"}) sym]").unwrap();
+        let upper = value.map_values(|v| match v {
+            Value::String(s) => Value::String(s.to_uppercase()),
+            v => v,
+        });
+        assert_eq!(parse("[\"AB\" (\"C\" {\"K\" \"V\"}) sym]").unwrap(), upper);
+
+        // children are rewritten before their parents see them
+        let mut seen = vec!();
+        parse("[1 [2]]").unwrap().map_values(|v| {
+            seen.push(v.to_string());
+            v
+        });
+        assert_eq!(vec!("1", "2", "[2]", "[1 [2]]"), seen);
+    }
+
+    #[test]
+    fn merge_test() {
+        let value = |s| parse(s).unwrap();
+
+        assert_eq!(
+            Ok(value("{:port 9090 :host \"h\" :debug true}")),
+            value("{:port 8080 :host \"h\"}").merge(value("{:port 9090 :debug true}")));
+        assert_eq!(
+            Ok(value("{:db {:host \"h\" :pool {:size 10 :idle 2}} :tags [:b]}")),
+            value("{:db {:host \"h\" :pool {:size 5}} :tags [:a]}").merge(value("{:db {:pool {:size 10 :idle 2}} :tags [:b]}")));
+
+        // a map replaces a scalar, and the other way round
+        assert_eq!(Ok(value("{:a {:b 1} :c 2}")), value("{:a 1 :c {:d 1}}").merge(value("{:a {:b 1} :c 2}")));
+
+        assert_eq!(Err(String::from("expected map, found vector")), value("{:a 1}").merge(value("[1]")));
+        assert_eq!(Err(String::from("expected map, found integer")), value("1").merge(value("{:a 1}")));
+    }
+
+    #[test]
+    fn get_path_test() {
+        let value = parse("{:servers [{:host \"a\"} {:host \"b\" \"port\" 80}] :name \"app\"}").unwrap();
+
+        assert_eq!(Some(&Value::from("b")), value.get_path_str("servers/1/host"));
+        assert_eq!(
+            Some(&Value::Integer(80)),
+            value.get_path(&[PathSegment::Key(keyword("servers")), PathSegment::Index(1), PathSegment::Key(Value::from("port"))]));
+        assert_eq!(Some(&value), value.get_path_str(""));
+
+        assert_eq!(None, value.get_path_str("servers/2/host"));
+        assert_eq!(None, value.get_path_str("servers/0/port"));
+        assert_eq!(None, value.get_path_str("name/0"));
+        assert_eq!(None, value.get_path_str("servers/host"));
+    }
+
+    #[test]
+    fn from_test() {
+        assert_eq!(Value::Integer(42), Value::from(42));
+        assert_eq!(Value::Float(1.5), Value::from(1.5));
+        assert_eq!(Value::Bool(true), Value::from(true));
+        assert_eq!(Value::String(String::from("owned")), Value::from(String::from("owned")));
+        assert_eq!(Value::String(String::from("borrowed")), Value::from("borrowed"));
+        assert_eq!(Value::Vector(vec!()), Value::from(vec!()));
+
+        let nested = Value::from(vec!(Value::from(1), Value::from("two"), Value::from(vec!(Value::from(false)))));
+        assert_eq!("[1 \"two\" [false]]", nested.to_string());
+    }
+
+    #[test]
+    fn builder_test() {
+        let vector: Value = (1..4).map(Value::Integer).collect();
+        assert_eq!("[1 2 3]", vector.to_string());
+
+        let map = Value::map(vec!(
+            (keyword("name"), Value::String(String::from("edn"))),
+            (keyword("tags"), Value::set(vec!(Value::Symbol("a".into())))),
+            (keyword("args"), Value::list(vec!(Value::Nil, Value::vector(vec!()))))));
+        assert_eq!("{:name \"edn\" :tags #{a} :args (nil [])}", map.to_string());
+        assert_eq!(parse(&map.to_string()).unwrap(), map);
+    }
+
+    #[test]
+    fn into_iter_test() {
+        let vector = parse("[1 (2) :c]").unwrap();
+        let borrowed: Vec<String> = (&vector).into_iter().map(|v| v.to_string()).collect();
+        assert_eq!(vec!("1", "(2)", ":c"), borrowed);
+        assert_eq!(vec!(Value::Integer(1), parse("(2)").unwrap(), keyword("c")), vector.into_iter().collect::<Vec<_>>());
+
+        let map = parse("{:a 1 :b [2]}").unwrap();
+        assert_eq!(
+            vec!((&keyword("a"), &Value::Integer(1)), (&keyword("b"), &parse("[2]").unwrap())),
+            map.entries().collect::<Vec<_>>());
+        assert_eq!(0, (&map).into_iter().count());
+        assert_eq!(parse("[[:a 1] [:b [2]]]").unwrap().into_iter().collect::<Vec<_>>(), map.into_iter().collect::<Vec<_>>());
+        assert_eq!(0, Value::Integer(1).entries().count());
+
+        for scalar in &[Value::Nil, Value::Integer(1), Value::String(String::from("s"))] {
+            assert_eq!(0, scalar.into_iter().count());
+            assert_eq!(0, scalar.clone().into_iter().count());
+        }
+    }
+
+    #[test]
+    fn index_test() {
+        let value = parse("{:users [{:name \"ann\"} {:name \"bo\"}] :ids ((1 2) (3))}").unwrap();
+
+        let users = value.get(&keyword("users")).unwrap();
+        assert_eq!(Some(&Value::String(String::from("bo"))), users[1].get(&keyword("name")));
+        assert_eq!(Value::Integer(3), value.get(&keyword("ids")).unwrap()[1][0]);
+
+        assert_eq!(None, value.get(&keyword("missing")));
+        assert_eq!(None, users.get(&keyword("users")));
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_out_of_range_test() {
+        let _ = &parse("[1 2]").unwrap()[2];
+    }
+
+    #[test]
+    fn display_test() {
+        for s in &["(1 :a nil)", "[]", "[true [1.5 2.0] \\a \\newline]", "{:a 1 :ns/b [x]}", "#{1 \"s\"}",
+                   "#inst \"1985-04-12T23:20:50.52Z\"", "#uuid \"f81d4fae-7dec-11d0-a765-00a0c91e6bf6\"",
+                   "#my/tag {}", "[12345678901234567890N 1.5M]"] {
+            assert_eq!(*s, parse(s).unwrap().to_string());
+        }
+
+        let value = Value::String(String::from("say \"hi\"\nbye\\"));
+        assert_eq!("\"say \\\"hi\\\"\\nbye\\\\\"", value.to_string());
+        assert_eq!(Ok(value.clone()), parse(&value.to_string()));
+    }
+
+    #[test]
+    fn from_str_test() {
+        assert_eq!(Ok(Value::Integer(42)), "42".parse::<Value>());
+        assert_eq!(
+            Ok(Value::Vector(vec![Value::Integer(1), Value::List(vec![keyword("a")])])),
+            "[1 (:a)]".parse());
+
+        let error = "[1 2".parse::<Value>().unwrap_err();
+        assert_eq!("unexpected end of input, expected `]`", error.message());
+    }
+
+    #[test]
+    fn ord_test() {
+        let mut values: Vec<Value> = ["{:a 1}", "#{2 1}", "[1 2]", "[1]", "(1)", "b", "a/b", ":b", ":a/b", ":a", "\"b\"",
+                                      "\"a\"", "\\a", "##NaN", "1.5M", "2N", "1.5", "3/2", "1", "-1", "true", "false", "nil",
+                                      "#t 1", "#uuid \"f81d4fae-7dec-11d0-a765-00a0c91e6bf6\"",
+                                      "#inst \"1985-04-12T23:20:50Z\""]
+            .iter().map(|s| parse(s).unwrap()).collect();
+        values.sort();
+
+        let sorted: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        assert_eq!(
+            vec!("nil", "false", "true", "-1", "1", "3/2", "1.5", "1.5M", "2N", "##NaN", "\\a", "\"a\"", "\"b\"", ":a", ":b",
+                 ":a/b", "a/b", "b", "#inst \"1985-04-12T23:20:50Z\"", "#uuid \"f81d4fae-7dec-11d0-a765-00a0c91e6bf6\"",
+                 "(1)", "[1]", "[1 2]", "#{2 1}", "{:a 1}", "#t 1"),
+            sorted);
+
+        // the order agrees with equality
+        assert_eq!(Ordering::Equal, parse("#{1 {:a 2 :b 3}}").unwrap().cmp(&parse("#{{:b 3 :a 2} 1}").unwrap()));
+        assert_eq!(Ordering::Equal, Value::Float(-0.0).cmp(&Value::Float(0.0)));
+        assert_eq!(Ordering::Less, Value::Integer(1).cmp(&Value::Float(1.0)));
+        assert!(Value::Integer(i64::MAX) < Value::BigInteger(String::from("9223372036854775808")));
+        assert_eq!(Ordering::Equal, Value::Float(f64::NAN).cmp(&Value::Float(-f64::NAN)));
+
+        // big decimals too close for `f64` by their exact value
+        let mut decimals: Vec<Value> = ["1.00000000000000000002M", "-1.00000000000000000001M", "1.00000000000000000001M",
+                                        "-1.00000000000000000002M", "0.0M", "-0.0M", "1.00000000000000000001e1M", "10M"]
+            .iter().map(|s| parse(s).unwrap()).collect();
+        decimals.sort();
+        assert_eq!(
+            vec!("-1.00000000000000000002M", "-1.00000000000000000001M", "-0.0M", "0.0M", "1.00000000000000000001M",
+                 "1.00000000000000000002M", "10M", "1.00000000000000000001e1M"),
+            decimals.iter().map(|value| value.to_string()).collect::<Vec<_>>());
+
+        // instants by the moment they name
+        let instant = |s| parse(s).unwrap();
+        assert!(instant("#inst \"1985-04-12T23:20:50+02:00\"") < instant("#inst \"1985-04-12T22:20:50Z\""));
+        assert!(instant("#inst \"1969-12-31T23:59:59Z\"") < instant("#inst \"1970-01-01T00:00:00Z\""));
+        assert!(instant("#inst \"2000-03-01T00:00:00Z\"") > instant("#inst \"2000-02-29T23:59:59.9Z\""));
+    }
+}
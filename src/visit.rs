@@ -0,0 +1,156 @@
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap;
+
+use Value;
+
+/// Callbacks for `walk`, one per kind of value. Each is called before the
+/// value's children are walked and does nothing by default, so a visitor only
+/// implements the kinds it cares about.
+pub trait Visitor {
+    /// Everything other than a collection or tagged value.
+    fn visit_scalar(&mut self, _value: &Value) {}
+    fn visit_list(&mut self, _items: &[Value]) {}
+    fn visit_vector(&mut self, _items: &[Value]) {}
+    fn visit_map(&mut self, _pairs: &[(Value, Value)]) {}
+    #[cfg(feature = "indexmap")]
+    fn visit_indexed_map(&mut self, _map: &IndexMap<Value, Value>) {}
+    fn visit_set(&mut self, _items: &[Value]) {}
+    fn visit_tagged(&mut self, _tag: &str, _value: &Value) {}
+}
+
+/// Visits `value` and everything inside it in document order, map keys
+/// before their values.
+pub fn walk<V: Visitor>(value: &Value, visitor: &mut V) {
+    match *value {
+        Value::List(ref items) => {
+            visitor.visit_list(items);
+            walk_items(items, visitor);
+        },
+        Value::Vector(ref items) => {
+            visitor.visit_vector(items);
+            walk_items(items, visitor);
+        },
+        Value::Set(ref items) => {
+            visitor.visit_set(items);
+            walk_items(items, visitor);
+        },
+        Value::Map(ref pairs) => {
+            visitor.visit_map(pairs);
+
+            for (key, value) in pairs {
+                walk(key, visitor);
+                walk(value, visitor);
+            }
+        },
+        #[cfg(feature = "indexmap")]
+        Value::IndexedMap(ref map) => {
+            visitor.visit_indexed_map(map);
+
+            for (key, value) in map {
+                walk(key, visitor);
+                walk(value, visitor);
+            }
+        },
+        Value::Tagged { ref tag, ref value } => {
+            visitor.visit_tagged(tag, value);
+            walk(value, visitor);
+        },
+        ref scalar => visitor.visit_scalar(scalar),
+    }
+}
+
+fn walk_items<V: Visitor>(items: &[Value], visitor: &mut V) {
+    for item in items {
+        walk(item, visitor);
+    }
+}
+
+#[cfg(feature = "std")]
+impl Value {
+    /// Every distinct keyword anywhere in the value, map keys included.
+    pub fn collect_keywords(&self) -> HashSet<Value> {
+        let mut collector = KeywordCollector { keywords: HashSet::new() };
+        walk(self, &mut collector);
+        collector.keywords
+    }
+}
+
+#[cfg(feature = "std")]
+struct KeywordCollector {
+    keywords: HashSet<Value>,
+}
+
+#[cfg(feature = "std")]
+impl Visitor for KeywordCollector {
+    fn visit_scalar(&mut self, value: &Value) {
+        if let Value::Keyword { .. } = *value {
+            self.keywords.insert(value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeMap;
+
+    use super::*;
+    use parse;
+    use prelude::*;
+
+    #[derive(Default)]
+    struct Counter {
+        counts: BTreeMap<&'static str, usize>,
+        keywords: Vec<String>,
+    }
+
+    impl Counter {
+        fn count(&mut self, kind: &'static str) {
+            *self.counts.entry(kind).or_insert(0) += 1;
+        }
+    }
+
+    impl Visitor for Counter {
+        fn visit_scalar(&mut self, value: &Value) {
+            self.count(value.type_name());
+
+            if let Value::Keyword { .. } = *value {
+                self.keywords.push(value.to_string());
+            }
+        }
+
+        fn visit_list(&mut self, _items: &[Value]) { self.count("list"); }
+        fn visit_vector(&mut self, _items: &[Value]) { self.count("vector"); }
+        fn visit_map(&mut self, _pairs: &[(Value, Value)]) { self.count("map"); }
+        fn visit_set(&mut self, _items: &[Value]) { self.count("set"); }
+        fn visit_tagged(&mut self, _tag: &str, _value: &Value) { self.count("tagged"); }
+    }
+
+    #[test]
+    fn walk_test() {
+        let value = parse("{:a [1 2 (x \"s\")] :b #{:c} :d #point {:x 1.5}}").unwrap();
+        let mut counter = Counter::default();
+        walk(&value, &mut counter);
+
+        let expected: BTreeMap<&str, usize> = vec!(
+            ("map", 2), ("vector", 1), ("list", 1), ("set", 1), ("tagged", 1),
+            ("keyword", 5), ("integer", 2), ("symbol", 1), ("string", 1), ("float", 1),
+        ).into_iter().collect();
+
+        assert_eq!(expected, counter.counts);
+        assert_eq!(vec!(":a", ":b", ":c", ":d", ":x"), counter.keywords);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn collect_keywords_test() {
+        let value = parse("{:a [:b {:a 1 :ns/a #tag :b}] :c #{:ns/a \"d\" e} :f ::g}").unwrap();
+        let mut keywords: Vec<String> = value.collect_keywords().iter().map(|k| k.to_string()).collect();
+        keywords.sort();
+
+        assert_eq!(vec!("::g", ":a", ":b", ":c", ":f", ":ns/a"), keywords);
+        assert!(parse("[1 \"a\" b]").unwrap().collect_keywords().is_empty());
+    }
+}